@@ -29,8 +29,26 @@
 
 #[cfg(not(windows))]
 mod rustix;
+#[cfg(not(windows))]
+mod rustix_batch;
+#[cfg(not(windows))]
+mod rustix_builder;
+#[cfg(not(windows))]
+mod rustix_datagram;
+#[cfg(not(windows))]
+mod rustix_inherit;
+#[cfg(all(not(windows), feature = "mio"))]
+mod rustix_mio;
+#[cfg(not(windows))]
+mod rustix_seqpacket;
+#[cfg(not(windows))]
+mod rustix_split;
 #[cfg(all(unix, feature = "async-std"))]
 mod unix_async_std;
+#[cfg(all(unix, feature = "futures-io"))]
+mod unix_futures;
+#[cfg(all(unix, feature = "use_smol"))]
+mod unix_smol;
 #[cfg(all(unix, feature = "tokio"))]
 mod unix_tokio;
 #[cfg(windows)]
@@ -40,12 +58,36 @@ mod windows_async_std;
 #[cfg(all(windows, feature = "tokio"))]
 mod windows_tokio;
 
+#[cfg(any(target_os = "android", target_os = "linux"))]
+pub use crate::rustix::UCred;
+#[cfg(unix)]
+pub use crate::rustix::{
+    socketpair_seqpacket, socketpair_stream, ControlTruncated, SocketpairStream, TryFromFdError,
+};
+#[cfg(unix)]
+pub use crate::rustix_builder::{SocketpairBuilder, SocketpairEndOptions, SocketpairKind};
+#[cfg(unix)]
+pub use crate::rustix_datagram::{socketpair_dgram, SocketpairDatagram};
+#[cfg(all(unix, feature = "mio"))]
+pub use crate::rustix_mio::mio_socketpair_stream;
+#[cfg(unix)]
+pub use crate::rustix_seqpacket::{socketpair_seqpacket_messages, SocketpairSeqpacket};
 #[cfg(unix)]
-pub use crate::rustix::{socketpair_seqpacket, socketpair_stream, SocketpairStream};
+pub use crate::rustix_split::{ReuniteError, SocketpairReadHalf, SocketpairWriteHalf};
 #[cfg(all(unix, feature = "async-std"))]
-pub use crate::unix_async_std::{async_std_socketpair_stream, AsyncStdSocketpairStream};
+pub use crate::unix_async_std::{
+    async_std_socketpair_seqpacket, async_std_socketpair_stream, AsyncStdSocketpairSeqpacket,
+    AsyncStdSocketpairStream,
+};
+#[cfg(all(unix, feature = "futures-io"))]
+pub use crate::unix_futures::{futures_socketpair_stream, FuturesSocketpairStream};
+#[cfg(all(unix, feature = "use_smol"))]
+pub use crate::unix_smol::{smol_socketpair_stream, SmolSocketpairStream};
 #[cfg(all(unix, feature = "tokio"))]
-pub use crate::unix_tokio::{tokio_socketpair_stream, TokioSocketpairStream};
+pub use crate::unix_tokio::{
+    tokio_socketpair_seqpacket, tokio_socketpair_stream, TokioSocketpairSeqpacket,
+    TokioSocketpairStream,
+};
 #[cfg(windows)]
 pub use crate::windows::{socketpair_seqpacket, socketpair_stream, SocketpairStream};
 #[cfg(all(windows, feature = "async-std"))]
@@ -1,12 +1,20 @@
 //! `SocketpairStream` and `socketpair_stream` for Posix-ish platforms.
 
+use crate::rustix_builder::{SocketpairBuilder, SocketpairKind};
+use crate::rustix_split::{split, SocketpairReadHalf, SocketpairWriteHalf};
 use io_extras::os::rustix::{
     AsRawFd, AsRawReadWriteFd, AsReadWriteFd, FromRawFd, IntoRawFd, RawFd,
 };
 use io_lifetimes::{AsFd, BorrowedFd, OwnedFd};
-use rustix::net::{AddressFamily, SocketFlags, SocketType};
-use std::fmt::{self, Arguments, Debug};
+use rustix::net::{
+    AddressFamily, RecvAncillaryBuffer, RecvAncillaryMessage, RecvFlags, ReturnFlags,
+    SendAncillaryBuffer, SendAncillaryMessage, SendFlags, SocketType,
+};
+use std::error::Error;
+use std::fmt::{self, Arguments, Debug, Display};
 use std::io::{self, IoSlice, IoSliceMut, Read, Write};
+use std::mem::MaybeUninit;
+use std::net::Shutdown;
 use std::os::unix::net::UnixStream;
 #[cfg(not(unix_socket_peek))]
 use {io_lifetimes::AsSocketlike, std::net::TcpStream};
@@ -23,6 +31,145 @@ impl SocketpairStream {
         self.0.try_clone().map(Self)
     }
 
+    /// Return the kind of socket this is, using `SO_TYPE`.
+    ///
+    /// On Darwin, sockets from [`socketpair_seqpacket`] use `SOCK_DGRAM`, so
+    /// they are reported as [`SocketpairKind::Datagram`].
+    #[inline]
+    pub fn kind(&self) -> io::Result<SocketpairKind> {
+        socket_kind(self.as_fd())
+    }
+
+    /// Receives data into `buf`, reporting whether a message was truncated.
+    /// On success, returns the number of bytes received and whether the
+    /// message didn't fit in `buf`.
+    ///
+    /// On sockets from [`socketpair_seqpacket`], each call receives at most
+    /// one message, and the OS discards any part of it which doesn't fit in
+    /// `buf`. [`Read::read`] does this silently; this reports it using
+    /// `MSG_TRUNC`.
+    ///
+    /// This fails on stream sockets, which have no message boundaries. The
+    /// kind of socket isn't recorded, so each call queries `SO_TYPE` first,
+    /// which costs an extra system call. When receiving many messages, use a
+    /// [`SocketpairSeqpacket`], whose kind is checked once by
+    /// [`SocketpairSeqpacket::try_from_fd`].
+    ///
+    /// [`SocketpairSeqpacket`]: crate::SocketpairSeqpacket
+    /// [`SocketpairSeqpacket::try_from_fd`]: crate::SocketpairSeqpacket::try_from_fd
+    #[inline]
+    pub fn recv(&self, buf: &mut [u8]) -> io::Result<(usize, bool)> {
+        self.check_packet()?;
+        crate::rustix_seqpacket::recv_msg(
+            self.as_fd(),
+            &mut [IoSliceMut::new(buf)],
+            RecvFlags::empty(),
+        )
+    }
+
+    /// Like [`recv`], but fails with [`io::ErrorKind::InvalidData`] if a
+    /// message was truncated, so that a buffer which is too small shows up as
+    /// an error instead of as corrupt data. The truncated message is still
+    /// consumed.
+    ///
+    /// [`recv`]: Self::recv
+    #[inline]
+    pub fn recv_strict(&self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.recv(buf)? {
+            (_, true) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "message was truncated",
+            )),
+            (n, false) => Ok(n),
+        }
+    }
+
+    /// Receives a single message into `buf`, telling an empty message apart
+    /// from the end of the stream, for sockets from
+    /// [`socketpair_seqpacket`]. See
+    /// [`SocketpairSeqpacket::recv_message`] for details.
+    ///
+    /// Like [`recv`], this fails on stream sockets, and checks the kind of
+    /// socket with an extra system call each time.
+    ///
+    /// [`recv`]: Self::recv
+    /// [`SocketpairSeqpacket::recv_message`]: crate::SocketpairSeqpacket::recv_message
+    #[inline]
+    pub fn recv_message(&self, buf: &mut [u8]) -> io::Result<Option<(usize, bool)>> {
+        self.check_packet()?;
+        crate::rustix_seqpacket::recv_message(self.as_fd(), buf)
+    }
+
+    /// Return the length of the next message in the queue, for sockets from
+    /// [`socketpair_seqpacket`], waiting for one to arrive if there is none,
+    /// or `None` if the peer has closed the connection, as with
+    /// [`recv_message`].
+    ///
+    /// Unlike [`num_ready_bytes`], which reports the total over all queued
+    /// messages, this is exactly the size of buffer needed to receive the
+    /// next message without truncation. On Linux, this uses
+    /// `recv(MSG_PEEK | MSG_TRUNC)`.
+    ///
+    /// Like [`recv`], this fails on stream sockets, and checks the kind of
+    /// socket with an extra system call each time.
+    ///
+    /// [`recv`]: Self::recv
+    /// [`recv_message`]: Self::recv_message
+    /// [`num_ready_bytes`]: Self::num_ready_bytes
+    #[inline]
+    pub fn next_message_len(&self) -> io::Result<Option<usize>> {
+        self.check_packet()?;
+        crate::rustix_seqpacket::next_message_len(self.as_fd())
+    }
+
+    /// Receives a single message into a newly allocated `Vec` which is sized
+    /// to hold the whole message, for sockets from [`socketpair_seqpacket`],
+    /// or returns `None` if the peer has closed the connection, as with
+    /// [`recv_message`].
+    ///
+    /// Like [`recv`], this fails on stream sockets, and checks the kind of
+    /// socket with an extra system call each time.
+    ///
+    /// [`recv`]: Self::recv
+    /// [`recv_message`]: Self::recv_message
+    #[inline]
+    pub fn recv_message_vec(&self) -> io::Result<Option<Vec<u8>>> {
+        self.check_packet()?;
+        crate::rustix_seqpacket::recv_vec(self.as_fd())
+    }
+
+    /// Fail if this is a stream socket, for methods which need message
+    /// boundaries.
+    fn check_packet(&self) -> io::Result<()> {
+        match self.kind()? {
+            SocketpairKind::Stream => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "not a packet socket",
+            )),
+            SocketpairKind::Seqpacket | SocketpairKind::Datagram => Ok(()),
+        }
+    }
+
+    /// Creates a `SocketpairStream` from `fd`, checking that it is a
+    /// connected `AF_UNIX` socket.
+    ///
+    /// Unlike `From<OwnedFd>`, this catches regular files, sockets in other
+    /// domains, and listening or unconnected sockets. Seqpacket sockets are
+    /// accepted, since [`socketpair_seqpacket`] returns `SocketpairStream`s;
+    /// use [`SocketpairSeqpacket::try_from_fd`] to get a handle with packet
+    /// methods, which rejects stream sockets.
+    ///
+    /// [`SocketpairSeqpacket::try_from_fd`]: crate::SocketpairSeqpacket::try_from_fd
+    ///
+    /// On failure, the error contains `fd`, unchanged.
+    #[inline]
+    pub fn try_from_fd(fd: OwnedFd) -> Result<Self, TryFromFdError> {
+        match check_socketpair_fd(fd.as_fd()) {
+            Ok(_) => Ok(Self::from(fd)),
+            Err(err) => Err(TryFromFdError(fd, err)),
+        }
+    }
+
     /// Receives data on the socket from the remote address to which it is
     /// connected, without removing that data from the queue. On success,
     /// returns the number of bytes peeked.
@@ -46,51 +193,363 @@ impl SocketpairStream {
     pub fn num_ready_bytes(&self) -> io::Result<u64> {
         Ok(rustix::io::ioctl_fionread(self)?)
     }
+
+    /// Reads data without waiting, even if the stream is in blocking mode.
+    /// On success, returns the number of bytes read.
+    ///
+    /// If no data is available, this fails with
+    /// [`io::ErrorKind::WouldBlock`].
+    #[inline]
+    pub fn try_read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        Ok(rustix::net::recv(self, buf, RecvFlags::DONTWAIT)?.0)
+    }
+
+    /// Writes data without waiting, even if the stream is in blocking mode.
+    /// On success, returns the number of bytes written.
+    ///
+    /// If the send buffer is full, this fails with
+    /// [`io::ErrorKind::WouldBlock`].
+    #[inline]
+    pub fn try_write(&self, buf: &[u8]) -> io::Result<usize> {
+        Ok(rustix::net::send(self, buf, SendFlags::DONTWAIT)?)
+    }
+
+    /// Shuts down the read half, write half, or both halves of this
+    /// connection.
+    ///
+    /// Shutting down the write half sends an end-of-stream to the peer, while
+    /// still allowing this end to read the peer's reply.
+    #[inline]
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.0.shutdown(how)
+    }
+
+    /// Splits this stream into owned read and write halves, which can be
+    /// used from different threads.
+    ///
+    /// Dropping the write half shuts down the write direction. The halves
+    /// can be put back together with [`SocketpairReadHalf::reunite`].
+    ///
+    /// [`SocketpairReadHalf::reunite`]: crate::SocketpairReadHalf::reunite
+    #[inline]
+    pub fn into_split(self) -> (SocketpairReadHalf, SocketpairWriteHalf) {
+        split(self)
+    }
+
+    /// Sends `buf` along with the file descriptors in `fds`, using
+    /// `SCM_RIGHTS`. On success, returns the number of bytes sent.
+    ///
+    /// The receiver gets its own descriptors referring to the same open
+    /// files. `buf` should not be empty, because the descriptors travel with
+    /// the data; on a stream socket they are delivered with the first byte
+    /// of `buf`.
+    #[inline]
+    pub fn send_with_fds(&self, buf: &[u8], fds: &[BorrowedFd<'_>]) -> io::Result<usize> {
+        send_with_fds(self.as_fd(), buf, fds)
+    }
+
+    /// Receives data into `buf` along with any file descriptors sent with it
+    /// using `SCM_RIGHTS`, which are appended to `fds`. On success, returns
+    /// the number of bytes received.
+    ///
+    /// The received descriptors have close-on-exec set. If the sender sent
+    /// more descriptors than fit in the control buffer, the OS closes the
+    /// excess and this returns an error wrapping [`ControlTruncated`]; the
+    /// descriptors that did fit are still appended to `fds`.
+    #[inline]
+    pub fn recv_with_fds(&self, buf: &mut [u8], fds: &mut Vec<OwnedFd>) -> io::Result<usize> {
+        recv_with_fds(self.as_fd(), buf, fds)
+    }
+
+    /// Return the credentials of the process which created the other end of
+    /// the socketpair, using `SO_PEERCRED`.
+    ///
+    /// These are recorded when the socketpair is created, so they don't
+    /// change if the other end is later passed to another process.
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    #[inline]
+    pub fn peer_credentials(&self) -> io::Result<UCred> {
+        peer_credentials(self.as_fd())
+    }
+
+    /// Enable or disable receiving the sender's credentials with each
+    /// message, using `SO_PASSCRED`.
+    ///
+    /// This must be enabled on the receiving end before a message is sent
+    /// for the message to carry credentials.
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    #[inline]
+    pub fn set_passcred(&self, passcred: bool) -> io::Result<()> {
+        Ok(rustix::net::sockopt::set_socket_passcred(self, passcred)?)
+    }
+
+    /// Return whether `SO_PASSCRED` is enabled.
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    #[inline]
+    pub fn passcred(&self) -> io::Result<bool> {
+        Ok(rustix::net::sockopt::socket_passcred(self)?)
+    }
+
+    /// Receives data into `buf` along with the credentials of the process
+    /// which sent it, using `SCM_CREDENTIALS`. On success, returns the number
+    /// of bytes received and the credentials, if any were attached.
+    ///
+    /// Unlike [`peer_credentials`], the credentials are verified by the
+    /// kernel for each message, so they identify the actual sender even if
+    /// the other end has been passed to another process. They are only
+    /// attached when [`set_passcred`] has been enabled on this end.
+    ///
+    /// This doesn't accept file descriptors. If the sender attached any, they
+    /// are closed, and this fails with [`io::ErrorKind::InvalidData`], or
+    /// with an error wrapping [`ControlTruncated`] if they didn't fit in the
+    /// control buffer. The data is still consumed.
+    ///
+    /// [`peer_credentials`]: Self::peer_credentials
+    /// [`set_passcred`]: Self::set_passcred
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    #[inline]
+    pub fn recv_with_credentials(&self, buf: &mut [u8]) -> io::Result<(usize, Option<UCred>)> {
+        recv_with_credentials(self.as_fd(), buf)
+    }
 }
 
-/// Create a socketpair and return stream handles connected to each end.
-#[inline]
-pub fn socketpair_stream() -> io::Result<(SocketpairStream, SocketpairStream)> {
-    // Darwin lacks `SOCK_CLOEXEC`.
+/// Credentials of a process, as reported by the OS.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UCred {
+    /// The process ID, in the same form as [`std::process::id`].
+    pub pid: u32,
+    /// The user ID.
+    pub uid: u32,
+    /// The group ID.
+    pub gid: u32,
+}
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+impl From<rustix::net::UCred> for UCred {
+    #[inline]
+    fn from(cred: rustix::net::UCred) -> Self {
+        Self {
+            pid: cred.pid.as_raw_nonzero().get() as u32,
+            uid: cred.uid.as_raw(),
+            gid: cred.gid.as_raw(),
+        }
+    }
+}
+
+/// Check that `socket` is a connected `AF_UNIX` socket, and return its kind.
+pub(crate) fn check_socketpair_fd(socket: BorrowedFd<'_>) -> io::Result<SocketpairKind> {
+    // Use `getsockname` rather than `SO_DOMAIN`, which isn't available
+    // everywhere.
+    if rustix::net::getsockname(socket)?.address_family() != AddressFamily::UNIX {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "not a Unix-domain socket",
+        ));
+    }
+
+    // This fails with `ENOTCONN` if the socket isn't connected.
+    rustix::net::getpeername(socket)?;
+
+    socket_kind(socket)
+}
+
+/// Return the kind of `socket`, using `SO_TYPE`.
+pub(crate) fn socket_kind(socket: BorrowedFd<'_>) -> io::Result<SocketpairKind> {
+    match rustix::net::sockopt::socket_type(socket)? {
+        SocketType::STREAM => Ok(SocketpairKind::Stream),
+        SocketType::SEQPACKET => Ok(SocketpairKind::Seqpacket),
+        SocketType::DGRAM => Ok(SocketpairKind::Datagram),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "unsupported socket type",
+        )),
+    }
+}
+
+/// Return the `SO_PEERCRED` credentials of `socket`.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+pub(crate) fn peer_credentials(socket: BorrowedFd<'_>) -> io::Result<UCred> {
+    Ok(rustix::net::sockopt::socket_peercred(socket)?.into())
+}
+
+/// Receive into `buf` from `socket`, along with any `SCM_CREDENTIALS`.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+pub(crate) fn recv_with_credentials(
+    socket: BorrowedFd<'_>,
+    buf: &mut [u8],
+) -> io::Result<(usize, Option<UCred>)> {
+    let mut space = [MaybeUninit::uninit(); rustix::cmsg_space!(ScmCredentials(1))];
+    let mut control = RecvAncillaryBuffer::new(&mut space);
+
+    let msg = rustix::net::recvmsg(
+        socket,
+        &mut [IoSliceMut::new(buf)],
+        &mut control,
+        RecvFlags::CMSG_CLOEXEC,
+    )?;
+
+    let mut cred = None;
+    let mut got_fds = false;
+    for message in control.drain() {
+        match message {
+            RecvAncillaryMessage::ScmCredentials(received) => cred = Some(received.into()),
+            // Dropping the iterator closes the descriptors.
+            RecvAncillaryMessage::ScmRights(_) => got_fds = true,
+            _ => {}
+        }
+    }
+
+    if msg.flags.contains(ReturnFlags::CTRUNC) {
+        return Err(io::Error::other(ControlTruncated {
+            received: msg.bytes,
+        }));
+    }
+    if got_fds {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "received unexpected file descriptors",
+        ));
+    }
+
+    Ok((msg.bytes, cred))
+}
+
+/// The maximum number of file descriptors which may be received in a single
+/// call to `recv_with_fds`. This is Linux's `SCM_MAX_FD`.
+const MAX_RECV_FDS: usize = 253;
+
+/// The error returned when the ancillary data received with a message didn't
+/// fit in the control buffer and was truncated by the OS (`MSG_CTRUNC`).
+///
+/// This is wrapped in an [`io::Error`]; use [`io::Error::get_ref`] and
+/// [`downcast_ref`] to detect it.
+///
+/// [`downcast_ref`]: https://doc.rust-lang.org/std/error/trait.Error.html#method.downcast_ref
+#[derive(Debug)]
+pub struct ControlTruncated {
+    received: usize,
+}
+
+impl ControlTruncated {
+    /// Return the number of data bytes which were received along with the
+    /// truncated ancillary data.
+    #[inline]
+    pub fn received(&self) -> usize {
+        self.received
+    }
+}
+
+impl Display for ControlTruncated {
+    #[allow(clippy::missing_inline_in_public_items)]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("ancillary data was truncated")
+    }
+}
+
+impl Error for ControlTruncated {}
+
+/// The error returned when a file descriptor isn't the kind of socket that
+/// was expected. It contains the file descriptor, unchanged, and the reason
+/// it was rejected.
+#[derive(Debug)]
+pub struct TryFromFdError(pub OwnedFd, pub io::Error);
+
+impl Display for TryFromFdError {
+    #[allow(clippy::missing_inline_in_public_items)]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt(&self.1, f)
+    }
+}
+
+impl Error for TryFromFdError {
+    #[allow(clippy::missing_inline_in_public_items)]
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.1)
+    }
+}
+
+impl From<TryFromFdError> for io::Error {
+    #[inline]
+    fn from(err: TryFromFdError) -> Self {
+        err.1
+    }
+}
+
+/// Send `buf` on `socket` with `fds` attached as `SCM_RIGHTS`.
+pub(crate) fn send_with_fds(
+    socket: BorrowedFd<'_>,
+    buf: &[u8],
+    fds: &[BorrowedFd<'_>],
+) -> io::Result<usize> {
+    let msg = SendAncillaryMessage::ScmRights(fds);
+    let mut space = vec![MaybeUninit::uninit(); msg.size()];
+    let mut control = SendAncillaryBuffer::new(&mut space);
+    // Don't attach an empty `SCM_RIGHTS` message.
+    if !fds.is_empty() && !control.push(msg) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "too many file descriptors",
+        ));
+    }
+    Ok(rustix::net::sendmsg(
+        socket,
+        &[IoSlice::new(buf)],
+        &mut control,
+        SendFlags::empty(),
+    )?)
+}
+
+/// Receive into `buf` from `socket`, appending any `SCM_RIGHTS` descriptors
+/// to `fds`.
+pub(crate) fn recv_with_fds(
+    socket: BorrowedFd<'_>,
+    buf: &mut [u8],
+    fds: &mut Vec<OwnedFd>,
+) -> io::Result<usize> {
+    let mut space = [MaybeUninit::uninit(); rustix::cmsg_space!(ScmRights(MAX_RECV_FDS))];
+    let mut control = RecvAncillaryBuffer::new(&mut space);
+
+    // Darwin lacks `MSG_CMSG_CLOEXEC`.
     #[cfg(not(any(target_os = "ios", target_os = "macos")))]
-    let socketflags = SocketFlags::CLOEXEC;
+    let flags = RecvFlags::CMSG_CLOEXEC;
     #[cfg(any(target_os = "ios", target_os = "macos"))]
-    let socketflags = SocketFlags::empty();
+    let flags = RecvFlags::empty();
+
+    let msg = rustix::net::recvmsg(socket, &mut [IoSliceMut::new(buf)], &mut control, flags)?;
 
-    let (a, b) =
-        rustix::net::socketpair(AddressFamily::UNIX, SocketType::STREAM, socketflags, None)?;
+    #[cfg(any(target_os = "ios", target_os = "macos"))]
+    let start = fds.len();
+    for message in control.drain() {
+        if let RecvAncillaryMessage::ScmRights(received) = message {
+            fds.extend(received);
+        }
+    }
 
-    // Darwin lacks `SOCK_CLOEXEC`.
+    // Darwin lacks `MSG_CMSG_CLOEXEC`. So we call `ioctl_fioclex` to emulate
+    // it.
     #[cfg(any(target_os = "ios", target_os = "macos"))]
-    {
-        rustix::io::ioctl_fioclex(&a)?;
-        rustix::io::ioctl_fioclex(&b)?;
+    for fd in &fds[start..] {
+        rustix::io::ioctl_fioclex(fd)?;
     }
 
-    Ok((
-        SocketpairStream::from(OwnedFd::from(a)),
-        SocketpairStream::from(OwnedFd::from(b)),
-    ))
+    if msg.flags.contains(ReturnFlags::CTRUNC) {
+        return Err(io::Error::other(ControlTruncated {
+            received: msg.bytes,
+        }));
+    }
+
+    Ok(msg.bytes)
 }
 
-/// Create a socketpair and return seqpacket handles connected to each end.
+/// Create a socketpair and return stream handles connected to each end.
 ///
-/// Seqpacket means that messages will not be split, and reads read at most one
-/// message each. Except on Windows where multiple messages may be read by a
-/// single read.
-#[cfg(not(any(target_os = "ios", target_os = "macos")))]
+/// To configure the sockets, use [`SocketpairBuilder`].
+///
+/// [`SocketpairBuilder`]: crate::SocketpairBuilder
 #[inline]
-pub fn socketpair_seqpacket() -> io::Result<(SocketpairStream, SocketpairStream)> {
-    let (a, b) = rustix::net::socketpair(
-        AddressFamily::UNIX,
-        SocketType::SEQPACKET,
-        SocketFlags::CLOEXEC,
-        None,
-    )?;
-    Ok((
-        SocketpairStream::from(OwnedFd::from(a)),
-        SocketpairStream::from(OwnedFd::from(b)),
-    ))
+pub fn socketpair_stream() -> io::Result<(SocketpairStream, SocketpairStream)> {
+    SocketpairBuilder::new().build()
 }
 
 /// Create a socketpair and return seqpacket handles connected to each end.
@@ -98,28 +557,19 @@ pub fn socketpair_seqpacket() -> io::Result<(SocketpairStream, SocketpairStream)
 /// Seqpacket means that messages will not be split, and reads read at most one
 /// message each. Except on Windows where multiple messages may be read by a
 /// single read.
-#[cfg(any(target_os = "ios", target_os = "macos"))]
+///
+/// The returned handles implement [`Read`] and [`Write`]. For an API which
+/// sends and receives whole messages and reports truncation, see
+/// [`socketpair_seqpacket_messages`]. To configure the sockets, use
+/// [`SocketpairBuilder`].
+///
+/// [`socketpair_seqpacket_messages`]: crate::socketpair_seqpacket_messages
+/// [`SocketpairBuilder`]: crate::SocketpairBuilder
 #[inline]
 pub fn socketpair_seqpacket() -> io::Result<(SocketpairStream, SocketpairStream)> {
-    // Darwin doesn't support `SEQPACKET` on `UNIX`-domain sockets, so we use
-    // `DGRAM` instead, which also provides packet-boundary semantics. `DGRAM`
-    // isn't reliable in general, but is commonly understood to be reliable
-    // in the `UNIX` domain.
-    //
-    // And, Darwin doesn't have `SOCK_CLOEXEC`. So we call `ioctl_fioclex`
-    // to emulate it.
-    let (a, b) = rustix::net::socketpair(
-        AddressFamily::UNIX,
-        SocketType::DGRAM,
-        SocketFlags::empty(),
-        None,
-    )?;
-    rustix::io::ioctl_fioclex(&a)?;
-    rustix::io::ioctl_fioclex(&b)?;
-    Ok((
-        SocketpairStream::from(OwnedFd::from(a)),
-        SocketpairStream::from(OwnedFd::from(b)),
-    ))
+    SocketpairBuilder::new()
+        .kind(SocketpairKind::Seqpacket)
+        .build()
 }
 
 impl Read for SocketpairStream {
@@ -155,6 +605,24 @@ impl Read for SocketpairStream {
     }
 }
 
+impl Read for &SocketpairStream {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&self.0).read(buf)
+    }
+
+    #[inline]
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut]) -> io::Result<usize> {
+        (&self.0).read_vectored(bufs)
+    }
+
+    #[cfg(can_vector)]
+    #[inline]
+    fn is_read_vectored(&self) -> bool {
+        (&self.0).is_read_vectored()
+    }
+}
+
 impl Write for SocketpairStream {
     #[inline]
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
@@ -194,6 +662,29 @@ impl Write for SocketpairStream {
     }
 }
 
+impl Write for &SocketpairStream {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&self.0).write(buf)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        (&self.0).flush()
+    }
+
+    #[inline]
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> io::Result<usize> {
+        (&self.0).write_vectored(bufs)
+    }
+
+    #[cfg(can_vector)]
+    #[inline]
+    fn is_write_vectored(&self) -> bool {
+        (&self.0).is_write_vectored()
+    }
+}
+
 impl AsRawFd for SocketpairStream {
     #[inline]
     fn as_raw_fd(&self) -> RawFd {
@@ -263,11 +754,14 @@ impl AsReadWriteFd for SocketpairStream {
 impl Debug for SocketpairStream {
     #[allow(clippy::missing_inline_in_public_items)]
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // Just print the fd numbers; don't try to print the path or any
-        // information about it, because this information is otherwise
-        // unavailable to safe Rust code.
-        f.debug_struct("SocketpairStream")
-            .field("raw_fd", &self.0.as_raw_fd())
-            .finish()
+        // Just print the fd numbers and the socket kind; don't try to print
+        // the path or any information about it, because this information is
+        // otherwise unavailable to safe Rust code.
+        let mut b = f.debug_struct("SocketpairStream");
+        b.field("raw_fd", &self.0.as_raw_fd());
+        if let Ok(kind) = self.kind() {
+            b.field("kind", &kind);
+        }
+        b.finish()
     }
 }
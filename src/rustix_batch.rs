@@ -0,0 +1,148 @@
+//! Batched message I/O for Posix-ish platforms.
+//!
+//! On Linux, this uses `sendmmsg` and `recvmmsg` to send or receive many
+//! messages in one system call. Elsewhere, it falls back to one call per
+//! message.
+
+use crate::rustix_seqpacket::at_end_of_stream;
+use io_lifetimes::BorrowedFd;
+use rustix::net::SendFlags;
+use std::io;
+#[cfg(not(target_os = "linux"))]
+use {crate::rustix_seqpacket::recv_msg, rustix::net::RecvFlags, std::io::IoSliceMut};
+#[cfg(target_os = "linux")]
+use {
+    io_extras::os::rustix::AsRawFd,
+    rustix::net::{MMsgHdr, SendAncillaryBuffer},
+    std::io::IoSlice,
+};
+
+/// Send each of `msgs` as a separate message, returning the number of
+/// messages sent.
+#[cfg(target_os = "linux")]
+pub(crate) fn send_batch(fd: BorrowedFd<'_>, msgs: &[&[u8]]) -> io::Result<usize> {
+    if msgs.is_empty() {
+        return Ok(0);
+    }
+
+    let iovs = msgs
+        .iter()
+        .map(|msg| [IoSlice::new(msg)])
+        .collect::<Vec<_>>();
+    let mut controls = msgs
+        .iter()
+        .map(|_| SendAncillaryBuffer::default())
+        .collect::<Vec<_>>();
+    let mut hdrs = iovs
+        .iter()
+        .zip(controls.iter_mut())
+        .map(|(iov, control)| MMsgHdr::new(iov, control))
+        .collect::<Vec<_>>();
+    Ok(rustix::net::sendmmsg(fd, &mut hdrs, SendFlags::empty())?)
+}
+
+/// Send each of `msgs` as a separate message, returning the number of
+/// messages sent.
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn send_batch(fd: BorrowedFd<'_>, msgs: &[&[u8]]) -> io::Result<usize> {
+    // Like `sendmmsg`, only fail if no messages were sent.
+    for (i, msg) in msgs.iter().enumerate() {
+        if let Err(err) = rustix::net::send(fd, msg, SendFlags::empty()) {
+            return if i == 0 { Err(err.into()) } else { Ok(i) };
+        }
+    }
+    Ok(msgs.len())
+}
+
+/// Receive messages into `bufs`, one per buffer, waiting for the first one
+/// but not for the rest. Returns the length of each message received and
+/// whether it was truncated, stopping at the end of the stream.
+#[cfg(target_os = "linux")]
+pub(crate) fn recv_batch(
+    fd: BorrowedFd<'_>,
+    bufs: &mut [&mut [u8]],
+) -> io::Result<Vec<(usize, bool)>> {
+    if bufs.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // rustix doesn't have `recvmmsg`, so use libc.
+    let mut iovs = bufs
+        .iter_mut()
+        .map(|buf| libc::iovec {
+            iov_base: buf.as_mut_ptr().cast(),
+            iov_len: buf.len(),
+        })
+        .collect::<Vec<_>>();
+    let mut hdrs = iovs
+        .iter_mut()
+        .map(|iov| {
+            // SAFETY: `mmsghdr` is a plain C struct, for which all zeros is
+            // a valid value.
+            let mut hdr: libc::mmsghdr = unsafe { std::mem::zeroed() };
+            hdr.msg_hdr.msg_iov = iov;
+            hdr.msg_hdr.msg_iovlen = 1;
+            hdr
+        })
+        .collect::<Vec<_>>();
+
+    // SAFETY: Each header points to one `iovec`, which points to a buffer
+    // we have exclusive access to, and they all outlive the call.
+    let n = unsafe {
+        libc::recvmmsg(
+            fd.as_raw_fd(),
+            hdrs.as_mut_ptr(),
+            hdrs.len().try_into().unwrap_or(libc::c_uint::MAX),
+            libc::MSG_WAITFORONE as _,
+            std::ptr::null_mut(),
+        )
+    };
+    if n < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut results = hdrs[..n as usize]
+        .iter()
+        .map(|hdr| {
+            (
+                hdr.msg_len as usize,
+                hdr.msg_hdr.msg_flags & libc::MSG_TRUNC != 0,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    // At the end of the stream, `recvmmsg` fills every remaining buffer with
+    // a zero-length result, so drop them.
+    if results.last() == Some(&(0, false)) && at_end_of_stream(fd)? {
+        while results.last() == Some(&(0, false)) {
+            results.pop();
+        }
+    }
+    Ok(results)
+}
+
+/// Receive messages into `bufs`, one per buffer, waiting for the first one
+/// but not for the rest. Returns the length of each message received and
+/// whether it was truncated, stopping at the end of the stream.
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn recv_batch(
+    fd: BorrowedFd<'_>,
+    bufs: &mut [&mut [u8]],
+) -> io::Result<Vec<(usize, bool)>> {
+    let mut results = Vec::with_capacity(bufs.len());
+    for (i, buf) in bufs.iter_mut().enumerate() {
+        // Like `MSG_WAITFORONE`, only wait for the first message.
+        let flags = if i == 0 {
+            RecvFlags::empty()
+        } else {
+            RecvFlags::DONTWAIT
+        };
+        match recv_msg(fd, &mut [IoSliceMut::new(buf)], flags) {
+            Ok((0, false)) if at_end_of_stream(fd)? => break,
+            Ok(result) => results.push(result),
+            Err(_) if i != 0 => break,
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(results)
+}
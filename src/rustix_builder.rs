@@ -0,0 +1,224 @@
+//! `SocketpairBuilder` for Posix-ish platforms.
+
+use crate::rustix::SocketpairStream;
+use io_lifetimes::OwnedFd;
+use rustix::io::FdFlags;
+use rustix::net::{AddressFamily, SocketFlags, SocketType};
+use std::io;
+
+/// The kind of socket in a socketpair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SocketpairKind {
+    /// A bidirectional bytestream, using `SOCK_STREAM`.
+    Stream,
+
+    /// A reliable message channel which preserves message boundaries, using
+    /// `SOCK_SEQPACKET`.
+    ///
+    /// On Darwin, which doesn't support `SOCK_SEQPACKET` in the `UNIX`
+    /// domain, this uses `SOCK_DGRAM` instead.
+    Seqpacket,
+
+    /// A datagram channel, using `SOCK_DGRAM`.
+    Datagram,
+}
+
+/// Options for one end of a socketpair created by a [`SocketpairBuilder`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SocketpairEndOptions {
+    cloexec: bool,
+    nonblocking: bool,
+    send_buffer_size: Option<usize>,
+    recv_buffer_size: Option<usize>,
+}
+
+impl SocketpairEndOptions {
+    /// Return the default options, which are close-on-exec, blocking, and
+    /// the OS's default buffer sizes.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            cloexec: true,
+            nonblocking: false,
+            send_buffer_size: None,
+            recv_buffer_size: None,
+        }
+    }
+
+    /// Set whether this end is closed on `exec`.
+    #[inline]
+    pub const fn cloexec(mut self, cloexec: bool) -> Self {
+        self.cloexec = cloexec;
+        self
+    }
+
+    /// Set whether this end is in nonblocking mode.
+    #[inline]
+    pub const fn nonblocking(mut self, nonblocking: bool) -> Self {
+        self.nonblocking = nonblocking;
+        self
+    }
+
+    /// Set the size of this end's send buffer, using `SO_SNDBUF`.
+    #[inline]
+    pub const fn send_buffer_size(mut self, size: usize) -> Self {
+        self.send_buffer_size = Some(size);
+        self
+    }
+
+    /// Set the size of this end's receive buffer, using `SO_RCVBUF`.
+    #[inline]
+    pub const fn recv_buffer_size(mut self, size: usize) -> Self {
+        self.recv_buffer_size = Some(size);
+        self
+    }
+}
+
+impl Default for SocketpairEndOptions {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A builder for configuring a socketpair before it is returned.
+///
+/// Close-on-exec is set when the sockets are created wherever the OS
+/// supports it, so a concurrent `fork` never sees an end which should have
+/// been close-on-exec without it.
+///
+/// ```rust
+/// use socketpair::{SocketpairBuilder, SocketpairEndOptions, SocketpairKind};
+///
+/// fn main() -> anyhow::Result<()> {
+///     let (_a, _b) = SocketpairBuilder::new()
+///         .kind(SocketpairKind::Seqpacket)
+///         .first(SocketpairEndOptions::new().nonblocking(true))
+///         .build()?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SocketpairBuilder {
+    kind: SocketpairKind,
+    first: SocketpairEndOptions,
+    second: SocketpairEndOptions,
+}
+
+impl SocketpairBuilder {
+    /// Return a builder for a [`SocketpairKind::Stream`] socketpair with
+    /// default options on both ends.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            kind: SocketpairKind::Stream,
+            first: SocketpairEndOptions::new(),
+            second: SocketpairEndOptions::new(),
+        }
+    }
+
+    /// Set the kind of socket to create.
+    #[inline]
+    pub const fn kind(mut self, kind: SocketpairKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Set the options for the first end of the pair.
+    #[inline]
+    pub const fn first(mut self, options: SocketpairEndOptions) -> Self {
+        self.first = options;
+        self
+    }
+
+    /// Set the options for the second end of the pair.
+    #[inline]
+    pub const fn second(mut self, options: SocketpairEndOptions) -> Self {
+        self.second = options;
+        self
+    }
+
+    /// Set the options for both ends of the pair.
+    #[inline]
+    pub const fn ends(self, options: SocketpairEndOptions) -> Self {
+        self.first(options).second(options)
+    }
+
+    /// Create the socketpair and return handles connected to each end.
+    pub fn build(&self) -> io::Result<(SocketpairStream, SocketpairStream)> {
+        // Darwin doesn't support `SEQPACKET` on `UNIX`-domain sockets, so we
+        // use `DGRAM` instead, which also provides packet-boundary semantics.
+        // `DGRAM` isn't reliable in general, but is commonly understood to be
+        // reliable in the `UNIX` domain.
+        let type_ = match self.kind {
+            SocketpairKind::Stream => SocketType::STREAM,
+            #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+            SocketpairKind::Seqpacket => SocketType::SEQPACKET,
+            #[cfg(any(target_os = "ios", target_os = "macos"))]
+            SocketpairKind::Seqpacket => SocketType::DGRAM,
+            SocketpairKind::Datagram => SocketType::DGRAM,
+        };
+
+        // Set `CLOEXEC` atomically if either end wants it; clearing it on the
+        // other end afterwards doesn't race with `fork`. Darwin lacks
+        // `SOCK_CLOEXEC` and `SOCK_NONBLOCK`, so there we set both options on
+        // each end afterwards.
+        #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+        let (cloexec, nonblocking) = (
+            self.first.cloexec || self.second.cloexec,
+            self.first.nonblocking && self.second.nonblocking,
+        );
+        #[cfg(any(target_os = "ios", target_os = "macos"))]
+        let (cloexec, nonblocking) = (false, false);
+
+        #[allow(unused_mut)]
+        let mut flags = SocketFlags::empty();
+        #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+        {
+            flags.set(SocketFlags::CLOEXEC, cloexec);
+            flags.set(SocketFlags::NONBLOCK, nonblocking);
+        }
+
+        let (a, b) = rustix::net::socketpair(AddressFamily::UNIX, type_, flags, None)?;
+        configure_end(&a, &self.first, cloexec, nonblocking)?;
+        configure_end(&b, &self.second, cloexec, nonblocking)?;
+
+        Ok((SocketpairStream::from(a), SocketpairStream::from(b)))
+    }
+}
+
+impl Default for SocketpairBuilder {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Apply `options` to `fd`, which was created with close-on-exec set to
+/// `cloexec` and nonblocking mode set to `nonblocking`.
+fn configure_end(
+    fd: &OwnedFd,
+    options: &SocketpairEndOptions,
+    cloexec: bool,
+    nonblocking: bool,
+) -> io::Result<()> {
+    if options.cloexec != cloexec {
+        if options.cloexec {
+            rustix::io::ioctl_fioclex(fd)?;
+        } else {
+            rustix::io::fcntl_setfd(fd, FdFlags::empty())?;
+        }
+    }
+    if options.nonblocking != nonblocking {
+        rustix::io::ioctl_fionbio(fd, options.nonblocking)?;
+    }
+
+    if let Some(size) = options.send_buffer_size {
+        rustix::net::sockopt::set_socket_send_buffer_size(fd, size)?;
+    }
+    if let Some(size) = options.recv_buffer_size {
+        rustix::net::sockopt::set_socket_recv_buffer_size(fd, size)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,197 @@
+//! `SocketpairDatagram` and `socketpair_dgram` for Posix-ish platforms.
+
+use crate::rustix::{check_socketpair_fd, TryFromFdError};
+use crate::rustix_builder::{SocketpairBuilder, SocketpairKind};
+use crate::rustix_seqpacket::recv_msg;
+use io_extras::os::rustix::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+use io_lifetimes::{AsFd, BorrowedFd, OwnedFd};
+use rustix::net::{RecvFlags, SendFlags};
+use std::fmt::{self, Debug};
+use std::io::{self, IoSliceMut};
+
+/// A socketpair datagram, which is a bidirectional datagram channel using
+/// `SOCK_DGRAM`.
+///
+/// Like a [`SocketpairSeqpacket`], each call to `send` sends exactly one
+/// message, and each call to `recv` receives at most one message. In the
+/// `UNIX` domain, datagrams are not lost or reordered, but unlike
+/// seqpacket sockets, datagram sockets are connectionless:
+///
+///  - There is no end-of-stream. When the peer is closed, `recv` waits
+///    forever, or fails with [`io::ErrorKind::WouldBlock`] in nonblocking
+///    mode, and `send` fails, with `ECONNREFUSED` on Linux.
+///  - When the peer's receive queue is full, Linux makes `send` wait, or
+///    fail with [`io::ErrorKind::WouldBlock`] in nonblocking mode, while
+///    Darwin and the BSDs may fail with `ENOBUFS` instead. The size of the
+///    send buffer, which limits the size of a single message, can be set
+///    with [`set_send_buffer_size`].
+///
+/// [`SocketpairSeqpacket`]: crate::SocketpairSeqpacket
+/// [`set_send_buffer_size`]: Self::set_send_buffer_size
+#[repr(transparent)]
+pub struct SocketpairDatagram(OwnedFd);
+
+impl SocketpairDatagram {
+    /// Creates a `SocketpairDatagram` from `fd`, checking that it is a
+    /// connected `AF_UNIX` datagram socket.
+    ///
+    /// On failure, the error contains `fd`, unchanged.
+    #[inline]
+    pub fn try_from_fd(fd: OwnedFd) -> Result<Self, TryFromFdError> {
+        let kind = match check_socketpair_fd(fd.as_fd()) {
+            Ok(kind) => kind,
+            Err(err) => return Err(TryFromFdError(fd, err)),
+        };
+        match kind {
+            SocketpairKind::Datagram => Ok(Self(fd)),
+            _ => Err(TryFromFdError(
+                fd,
+                io::Error::new(io::ErrorKind::InvalidInput, "not a datagram socket"),
+            )),
+        }
+    }
+
+    /// Creates a new independently owned handle to the underlying socket.
+    #[inline]
+    pub fn try_clone(&self) -> io::Result<Self> {
+        self.0.try_clone().map(Self)
+    }
+
+    /// Sends `buf` as a single datagram. On success, returns the number of
+    /// bytes sent.
+    #[inline]
+    pub fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        Ok(rustix::net::send(self, buf, SendFlags::empty())?)
+    }
+
+    /// Receives a single datagram into `buf`. On success, returns the number
+    /// of bytes received and whether the datagram was truncated because it
+    /// didn't fit in `buf`.
+    #[inline]
+    pub fn recv(&self, buf: &mut [u8]) -> io::Result<(usize, bool)> {
+        recv_msg(
+            self.as_fd(),
+            &mut [IoSliceMut::new(buf)],
+            RecvFlags::empty(),
+        )
+    }
+
+    /// Sends each of `msgs` as a separate datagram. On success, returns the
+    /// number of datagrams sent, which may be less than `msgs.len()`.
+    ///
+    /// On Linux, this uses `sendmmsg` to send them in one system call.
+    #[inline]
+    pub fn send_batch(&self, msgs: &[&[u8]]) -> io::Result<usize> {
+        crate::rustix_batch::send_batch(self.as_fd(), msgs)
+    }
+
+    /// Receives datagrams into `bufs`, one per buffer, waiting for the first
+    /// one to arrive but not for the rest. On success, returns the number of
+    /// bytes received and whether the datagram was truncated, for each
+    /// datagram received.
+    ///
+    /// On Linux, this uses `recvmmsg` to receive them in one system call.
+    #[inline]
+    pub fn recv_batch(&self, bufs: &mut [&mut [u8]]) -> io::Result<Vec<(usize, bool)>> {
+        crate::rustix_batch::recv_batch(self.as_fd(), bufs)
+    }
+
+    /// Receives a single datagram into `buf`, without removing it from the
+    /// queue. On success, returns the number of bytes peeked and whether the
+    /// datagram was longer than `buf`.
+    #[inline]
+    pub fn peek(&self, buf: &mut [u8]) -> io::Result<(usize, bool)> {
+        recv_msg(self.as_fd(), &mut [IoSliceMut::new(buf)], RecvFlags::PEEK)
+    }
+
+    /// Return the number of bytes which are ready to be read immediately.
+    #[inline]
+    pub fn num_ready_bytes(&self) -> io::Result<u64> {
+        Ok(rustix::io::ioctl_fionread(self)?)
+    }
+
+    /// Set the size of the send buffer, using `SO_SNDBUF`.
+    ///
+    /// Linux doubles the requested size to allow for bookkeeping overhead.
+    #[inline]
+    pub fn set_send_buffer_size(&self, size: usize) -> io::Result<()> {
+        Ok(rustix::net::sockopt::set_socket_send_buffer_size(
+            self, size,
+        )?)
+    }
+
+    /// Return the size of the send buffer, using `SO_SNDBUF`.
+    #[inline]
+    pub fn send_buffer_size(&self) -> io::Result<usize> {
+        Ok(rustix::net::sockopt::socket_send_buffer_size(self)?)
+    }
+}
+
+/// Create a socketpair and return datagram handles connected to each end.
+///
+/// To configure the sockets, use [`SocketpairBuilder`] with
+/// [`SocketpairKind::Datagram`].
+///
+/// [`SocketpairBuilder`]: crate::SocketpairBuilder
+/// [`SocketpairKind::Datagram`]: crate::SocketpairKind::Datagram
+#[inline]
+pub fn socketpair_dgram() -> io::Result<(SocketpairDatagram, SocketpairDatagram)> {
+    let (a, b) = SocketpairBuilder::new()
+        .kind(SocketpairKind::Datagram)
+        .build()?;
+    Ok((SocketpairDatagram(a.into()), SocketpairDatagram(b.into())))
+}
+
+impl AsRawFd for SocketpairDatagram {
+    #[inline]
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+impl AsFd for SocketpairDatagram {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+impl IntoRawFd for SocketpairDatagram {
+    #[inline]
+    fn into_raw_fd(self) -> RawFd {
+        self.0.into_raw_fd()
+    }
+}
+
+impl From<SocketpairDatagram> for OwnedFd {
+    #[inline]
+    fn from(datagram: SocketpairDatagram) -> OwnedFd {
+        datagram.0
+    }
+}
+
+impl FromRawFd for SocketpairDatagram {
+    #[inline]
+    unsafe fn from_raw_fd(raw_fd: RawFd) -> Self {
+        Self(OwnedFd::from_raw_fd(raw_fd))
+    }
+}
+
+impl From<OwnedFd> for SocketpairDatagram {
+    #[inline]
+    fn from(fd: OwnedFd) -> Self {
+        Self(fd)
+    }
+}
+
+impl Debug for SocketpairDatagram {
+    #[allow(clippy::missing_inline_in_public_items)]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Just print the fd numbers; don't try to print the path or any
+        // information about it, because this information is otherwise
+        // unavailable to safe Rust code.
+        f.debug_struct("SocketpairDatagram")
+            .field("raw_fd", &self.0.as_raw_fd())
+            .finish()
+    }
+}
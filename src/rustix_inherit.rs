@@ -0,0 +1,169 @@
+//! Passing a `SocketpairStream` to a child process on Posix-ish platforms.
+
+use crate::rustix::check_socketpair_fd;
+use crate::SocketpairStream;
+use io_extras::os::rustix::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+use io_lifetimes::{AsFd, OwnedFd};
+use std::env;
+use std::io;
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+
+impl SocketpairStream {
+    /// Arrange for each of `streams` to be installed as the paired file
+    /// descriptor number in the child process spawned by `command`.
+    ///
+    /// The streams are held by `command` until it is dropped, so the parent
+    /// should keep the other ends and drop `command` once the child has been
+    /// spawned. In the child, the target descriptors are not close-on-exec,
+    /// and no other descriptor is made inheritable. To tell the child where
+    /// to find a stream, pass its target along, for example in an
+    /// environment variable read by [`SocketpairStream::from_env`].
+    ///
+    /// Install all of the streams for a child with one call. Within a call,
+    /// the streams are moved out of the way of every target before any of
+    /// them is installed, so a target may be the number another stream has
+    /// in the parent. Separate calls don't know about each other's targets,
+    /// so an earlier call may overwrite a stream from a later one.
+    pub fn install_in_child<I>(command: &mut Command, streams: I) -> io::Result<()>
+    where
+        I: IntoIterator<Item = (SocketpairStream, RawFd)>,
+    {
+        let streams = streams
+            .into_iter()
+            .map(|(stream, target_fd)| (OwnedFd::from(stream), target_fd))
+            .collect::<Vec<_>>();
+        for (i, (_, target_fd)) in streams.iter().enumerate() {
+            if *target_fd < 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "target fd must not be negative",
+                ));
+            }
+            if streams[..i].iter().any(|(_, other)| other == target_fd) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "target fds must be distinct",
+                ));
+            }
+        }
+        let above_targets = streams
+            .iter()
+            .map(|(_, target_fd)| *target_fd)
+            .max()
+            .map_or(0, |max| max.saturating_add(1));
+
+        // Allocate up front, because the child must not allocate.
+        let mut moved = vec![-1; streams.len()];
+        let pre_exec = move || -> io::Result<()> {
+            // This runs between `fork` and `exec`, so it must only make
+            // async-signal-safe calls.
+            //
+            // SAFETY: `fcntl` and `dup2` only operate on descriptor numbers.
+            // The sources are open, because `streams` owns them, and the
+            // copies above the targets are fresh descriptors which nothing
+            // else in the child uses.
+            unsafe {
+                for ((fd, _), moved) in streams.iter().zip(moved.iter_mut()) {
+                    *moved = libc::fcntl(fd.as_raw_fd(), libc::F_DUPFD_CLOEXEC, above_targets);
+                    if *moved < 0 {
+                        return Err(io::Error::last_os_error());
+                    }
+                }
+                // `dup2` clears close-on-exec on the new descriptor.
+                for ((_, target_fd), moved) in streams.iter().zip(moved.iter()) {
+                    if libc::dup2(*moved, *target_fd) < 0 {
+                        return Err(io::Error::last_os_error());
+                    }
+                }
+            }
+            Ok(())
+        };
+
+        // SAFETY: `pre_exec` only calls `fcntl` and `dup2`, which are
+        // async-signal-safe, and doesn't allocate.
+        unsafe {
+            command.pre_exec(pre_exec);
+        }
+        Ok(())
+    }
+
+    /// Arrange for this stream to be both the stdin and the stdout of the
+    /// child process spawned by `command`.
+    ///
+    /// As with [`SocketpairStream::install_in_child`], the stream is held by
+    /// `command` until it is dropped, so drop `command` once the child has
+    /// been spawned, or the other end won't see an end-of-stream when the
+    /// child exits.
+    pub fn install_as_stdio(self, command: &mut Command) -> io::Result<()> {
+        command.stdin(self.try_clone()?).stdout(self);
+        Ok(())
+    }
+
+    /// Take ownership of the stream inherited from the parent process as
+    /// file descriptor `fd`, for example with
+    /// [`SocketpairStream::install_in_child`].
+    ///
+    /// This checks that `fd` is a connected `AF_UNIX` socket, and sets
+    /// close-on-exec on it so that it isn't inherited further.
+    ///
+    /// # Safety
+    ///
+    /// `fd` must be open and not owned by anything else in this process.
+    pub unsafe fn from_inherited_fd(fd: RawFd) -> io::Result<Self> {
+        let fd = OwnedFd::from_raw_fd(fd);
+        match check_inherited(&fd) {
+            Ok(()) => Ok(Self::from(fd)),
+            Err(err) => {
+                // The fd isn't what we expected, so don't close it.
+                let _ = fd.into_raw_fd();
+                Err(err)
+            }
+        }
+    }
+
+    /// Like [`SocketpairStream::from_inherited_fd`], but read the file
+    /// descriptor number from the environment variable `var`.
+    ///
+    /// # Safety
+    ///
+    /// The file descriptor named by `var` must be open and not owned by
+    /// anything else in this process.
+    pub unsafe fn from_env(var: &str) -> io::Result<Self> {
+        let value = env::var(var).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("environment variable {}: {}", var, err),
+            )
+        })?;
+        let fd = value.parse::<RawFd>().map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("environment variable {}: {}", var, err),
+            )
+        })?;
+        if fd < 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("environment variable {}: negative fd", var),
+            ));
+        }
+        Self::from_inherited_fd(fd)
+    }
+}
+
+/// Check that `fd` is a connected `AF_UNIX` socket, and set close-on-exec on
+/// it.
+fn check_inherited<Fd: AsFd>(fd: Fd) -> io::Result<()> {
+    let fd = fd.as_fd();
+    check_socketpair_fd(fd)?;
+    rustix::io::ioctl_fioclex(fd)?;
+    Ok(())
+}
+
+impl From<SocketpairStream> for Stdio {
+    #[inline]
+    fn from(stream: SocketpairStream) -> Stdio {
+        Stdio::from(OwnedFd::from(stream))
+    }
+}
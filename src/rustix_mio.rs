@@ -0,0 +1,45 @@
+//! `mio` support for `SocketpairStream` on Posix-ish platforms.
+
+use crate::rustix_builder::{SocketpairBuilder, SocketpairEndOptions};
+use crate::SocketpairStream;
+use io_extras::os::rustix::AsRawFd;
+use mio::event::Source;
+use mio::unix::SourceFd;
+use mio::{Interest, Registry, Token};
+use std::io;
+
+/// Create a socketpair and return stream handles connected to each end, in
+/// nonblocking mode, ready to be registered with a [`mio::Poll`].
+#[inline]
+pub fn mio_socketpair_stream() -> io::Result<(SocketpairStream, SocketpairStream)> {
+    SocketpairBuilder::new()
+        .ends(SocketpairEndOptions::new().nonblocking(true))
+        .build()
+}
+
+impl Source for SocketpairStream {
+    #[inline]
+    fn register(
+        &mut self,
+        registry: &Registry,
+        token: Token,
+        interests: Interest,
+    ) -> io::Result<()> {
+        SourceFd(&self.as_raw_fd()).register(registry, token, interests)
+    }
+
+    #[inline]
+    fn reregister(
+        &mut self,
+        registry: &Registry,
+        token: Token,
+        interests: Interest,
+    ) -> io::Result<()> {
+        SourceFd(&self.as_raw_fd()).reregister(registry, token, interests)
+    }
+
+    #[inline]
+    fn deregister(&mut self, registry: &Registry) -> io::Result<()> {
+        SourceFd(&self.as_raw_fd()).deregister(registry)
+    }
+}
@@ -0,0 +1,362 @@
+//! `SocketpairSeqpacket` for Posix-ish platforms.
+
+use crate::rustix::{check_socketpair_fd, socketpair_seqpacket, TryFromFdError};
+use crate::rustix_builder::SocketpairKind;
+use io_extras::os::rustix::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+use io_lifetimes::{AsFd, BorrowedFd, OwnedFd};
+use rustix::event::{PollFd, PollFlags, Timespec};
+use rustix::net::{RecvAncillaryBuffer, RecvFlags, ReturnFlags, SendAncillaryBuffer, SendFlags};
+use std::fmt::{self, Debug};
+use std::io::{self, IoSlice, IoSliceMut};
+
+/// A socketpair seqpacket, which is a bidirectional reliable message channel
+/// much like a [`SocketpairStream`] created by [`socketpair_seqpacket`],
+/// except that it exposes a message-oriented `send`/`recv` API instead of
+/// implementing [`Read`] and [`Write`].
+///
+/// Each call to `send` sends exactly one message, and each call to `recv`
+/// receives at most one message. If a message is longer than the buffer
+/// passed to `recv`, the excess bytes are discarded and the message is
+/// reported as truncated.
+///
+/// [`SocketpairStream`]: crate::SocketpairStream
+/// [`Read`]: std::io::Read
+/// [`Write`]: std::io::Write
+#[repr(transparent)]
+pub struct SocketpairSeqpacket(OwnedFd);
+
+impl SocketpairSeqpacket {
+    /// Create a socketpair and return seqpacket handles connected to each
+    /// end.
+    ///
+    /// This is the same as [`socketpair_seqpacket_messages`].
+    ///
+    /// [`socketpair_seqpacket_messages`]: crate::socketpair_seqpacket_messages
+    #[inline]
+    pub fn pair() -> io::Result<(Self, Self)> {
+        socketpair_seqpacket_messages()
+    }
+
+    /// Creates a `SocketpairSeqpacket` from `fd`, checking that it is a
+    /// connected `AF_UNIX` seqpacket socket.
+    ///
+    /// On Darwin, where [`socketpair_seqpacket`] uses `SOCK_DGRAM`, datagram
+    /// sockets are accepted too.
+    ///
+    /// On failure, the error contains `fd`, unchanged.
+    #[inline]
+    pub fn try_from_fd(fd: OwnedFd) -> Result<Self, TryFromFdError> {
+        let kind = match check_socketpair_fd(fd.as_fd()) {
+            Ok(kind) => kind,
+            Err(err) => return Err(TryFromFdError(fd, err)),
+        };
+        match kind {
+            SocketpairKind::Seqpacket => Ok(Self(fd)),
+            #[cfg(any(target_os = "ios", target_os = "macos"))]
+            SocketpairKind::Datagram => Ok(Self(fd)),
+            _ => Err(TryFromFdError(
+                fd,
+                io::Error::new(io::ErrorKind::InvalidInput, "not a seqpacket socket"),
+            )),
+        }
+    }
+
+    /// Creates a new independently owned handle to the underlying socket.
+    #[inline]
+    pub fn try_clone(&self) -> io::Result<Self> {
+        self.0.try_clone().map(Self)
+    }
+
+    /// Sends `buf` as a single message. On success, returns the number of
+    /// bytes sent.
+    #[inline]
+    pub fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        Ok(rustix::net::send(self, buf, SendFlags::empty())?)
+    }
+
+    /// Like [`send`], but gathers the message from a slice of buffers.
+    ///
+    /// [`send`]: Self::send
+    #[inline]
+    pub fn send_vectored(&self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        Ok(rustix::net::sendmsg(
+            self,
+            bufs,
+            &mut SendAncillaryBuffer::default(),
+            SendFlags::empty(),
+        )?)
+    }
+
+    /// Receives a single message into `buf`. On success, returns the number
+    /// of bytes received and whether the message was truncated because it
+    /// didn't fit in `buf`.
+    ///
+    /// A return value of `(0, false)` means either an empty message was
+    /// received or the peer has closed the connection.
+    #[inline]
+    pub fn recv(&self, buf: &mut [u8]) -> io::Result<(usize, bool)> {
+        self.recv_vectored(&mut [IoSliceMut::new(buf)])
+    }
+
+    /// Sends each of `msgs` as a separate message. On success, returns the
+    /// number of messages sent, which may be less than `msgs.len()`.
+    ///
+    /// On Linux, this uses `sendmmsg` to send them in one system call.
+    #[inline]
+    pub fn send_batch(&self, msgs: &[&[u8]]) -> io::Result<usize> {
+        crate::rustix_batch::send_batch(self.as_fd(), msgs)
+    }
+
+    /// Receives messages into `bufs`, one per buffer, waiting for the first
+    /// one to arrive but not for the rest. On success, returns the number of
+    /// bytes received and whether the message was truncated, for each
+    /// message received.
+    ///
+    /// Once the peer has closed the connection, this stops at the end of the
+    /// stream, so an empty result means the end of the stream was reached.
+    /// As with [`recv_message`], empty messages that are still queued after
+    /// the last non-empty message when the peer hangs up are dropped.
+    ///
+    /// On Linux, this uses `recvmmsg` to receive them in one system call.
+    ///
+    /// [`recv_message`]: Self::recv_message
+    #[inline]
+    pub fn recv_batch(&self, bufs: &mut [&mut [u8]]) -> io::Result<Vec<(usize, bool)>> {
+        crate::rustix_batch::recv_batch(self.as_fd(), bufs)
+    }
+
+    /// Receives a single message into `buf`, telling an empty message apart
+    /// from the end of the stream. On success, returns the number of bytes
+    /// received and whether the message was truncated, or `None` if the peer
+    /// has closed the connection or shut down its write direction.
+    ///
+    /// The end of the stream is detected by checking for `POLLHUP` (and
+    /// `POLLRDHUP` where available) when `recv` returns zero. Empty messages
+    /// that are still queued after the last non-empty message when the peer
+    /// hangs up can't be told apart from the end of the stream, and are
+    /// reported as `None`.
+    ///
+    /// On Darwin, where this is a `SOCK_DGRAM` socket, there is no
+    /// end-of-stream, so this never returns `None`, and waits forever once
+    /// the peer has closed the connection.
+    #[inline]
+    pub fn recv_message(&self, buf: &mut [u8]) -> io::Result<Option<(usize, bool)>> {
+        recv_message(self.as_fd(), buf)
+    }
+
+    /// Like [`recv`], but scatters the message into a slice of buffers.
+    ///
+    /// [`recv`]: Self::recv
+    #[inline]
+    pub fn recv_vectored(&self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<(usize, bool)> {
+        recv_msg(self.as_fd(), bufs, RecvFlags::empty())
+    }
+
+    /// Receives a single message into a newly allocated `Vec` which is sized
+    /// to hold the whole message, or returns `None` if the peer has closed
+    /// the connection, as with [`recv_message`].
+    ///
+    /// [`recv_message`]: Self::recv_message
+    #[inline]
+    pub fn recv_vec(&self) -> io::Result<Option<Vec<u8>>> {
+        recv_vec(self.as_fd())
+    }
+
+    /// Return the length of the next message in the queue, waiting for one
+    /// to arrive if there is none, or `None` if the peer has closed the
+    /// connection, as with [`recv_message`].
+    ///
+    /// Unlike [`num_ready_bytes`], this is exactly the size of buffer needed
+    /// to receive the next message without truncation.
+    ///
+    /// [`recv_message`]: Self::recv_message
+    /// [`num_ready_bytes`]: Self::num_ready_bytes
+    #[inline]
+    pub fn next_message_len(&self) -> io::Result<Option<usize>> {
+        next_message_len(self.as_fd())
+    }
+
+    /// Receives a single message into `buf`, without removing it from the
+    /// queue. On success, returns the number of bytes peeked and whether the
+    /// message was longer than `buf`.
+    #[inline]
+    pub fn peek(&self, buf: &mut [u8]) -> io::Result<(usize, bool)> {
+        recv_msg(self.as_fd(), &mut [IoSliceMut::new(buf)], RecvFlags::PEEK)
+    }
+
+    /// Return the number of bytes which are ready to be read immediately.
+    ///
+    /// This is the total over all queued messages, not the length of the
+    /// next message.
+    #[inline]
+    pub fn num_ready_bytes(&self) -> io::Result<u64> {
+        Ok(rustix::io::ioctl_fionread(self)?)
+    }
+}
+
+/// Create a socketpair and return [`SocketpairSeqpacket`] handles connected to
+/// each end.
+///
+/// This uses the same socket setup as [`socketpair_seqpacket`], which
+/// returns handles implementing [`Read`] and [`Write`] instead.
+///
+/// [`Read`]: std::io::Read
+/// [`Write`]: std::io::Write
+#[inline]
+pub fn socketpair_seqpacket_messages() -> io::Result<(SocketpairSeqpacket, SocketpairSeqpacket)> {
+    let (a, b) = socketpair_seqpacket()?;
+    Ok((SocketpairSeqpacket(a.into()), SocketpairSeqpacket(b.into())))
+}
+
+/// Receive one message with `recvmsg`, returning the number of bytes stored
+/// in `bufs` and whether the message was truncated.
+pub(crate) fn recv_msg(
+    fd: BorrowedFd<'_>,
+    bufs: &mut [IoSliceMut<'_>],
+    flags: RecvFlags,
+) -> io::Result<(usize, bool)> {
+    let msg = rustix::net::recvmsg(fd, bufs, &mut RecvAncillaryBuffer::default(), flags)?;
+    Ok((msg.bytes, msg.flags.contains(ReturnFlags::TRUNC)))
+}
+
+/// Receive one message into `buf`, returning `None` at the end of the stream.
+pub(crate) fn recv_message(
+    fd: BorrowedFd<'_>,
+    buf: &mut [u8],
+) -> io::Result<Option<(usize, bool)>> {
+    match recv_msg(fd, &mut [IoSliceMut::new(buf)], RecvFlags::empty())? {
+        (0, false) if at_end_of_stream(fd)? => Ok(None),
+        result => Ok(Some(result)),
+    }
+}
+
+/// Test whether a zero-length result from `recv` on `fd` means the end of
+/// the stream, rather than an empty message.
+pub(crate) fn at_end_of_stream(fd: BorrowedFd<'_>) -> io::Result<bool> {
+    // Once the peer has hung up, no more messages can arrive, so if any
+    // data is still queued, the zero-length result was a message.
+    Ok(peer_hung_up(fd)? && rustix::io::ioctl_fionread(fd)? == 0)
+}
+
+/// Receive one message into a `Vec` sized to hold it, returning `None` at the
+/// end of the stream.
+pub(crate) fn recv_vec(fd: BorrowedFd<'_>) -> io::Result<Option<Vec<u8>>> {
+    let len = match next_message_len(fd)? {
+        Some(len) => len,
+        None => return Ok(None),
+    };
+    let mut buf = vec![0_u8; len];
+    let (n, truncated) = recv_msg(fd, &mut [IoSliceMut::new(&mut buf)], RecvFlags::empty())?;
+    if truncated {
+        // Another handle to this socket received the message we measured,
+        // and the message we got instead is longer.
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "message was truncated",
+        ));
+    }
+    buf.truncate(n);
+    Ok(Some(buf))
+}
+
+/// Test whether the peer of `fd` has hung up or shut down its write
+/// direction, without waiting.
+fn peer_hung_up(fd: BorrowedFd<'_>) -> io::Result<bool> {
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    let hup = PollFlags::HUP | PollFlags::RDHUP;
+    #[cfg(not(any(target_os = "android", target_os = "linux")))]
+    let hup = PollFlags::HUP;
+
+    let mut fds = [PollFd::new(&fd, PollFlags::IN | hup)];
+    rustix::event::poll(&mut fds, Some(&Timespec::default()))?;
+    Ok(fds[0].revents().intersects(hup))
+}
+
+/// Return the length of the next message in the queue, waiting for one to
+/// arrive if there is none, or `None` at the end of the stream.
+pub(crate) fn next_message_len(fd: BorrowedFd<'_>) -> io::Result<Option<usize>> {
+    match peek_message_len(fd)? {
+        0 if at_end_of_stream(fd)? => Ok(None),
+        len => Ok(Some(len)),
+    }
+}
+
+/// Return the length of the next message in the queue, waiting for one to
+/// arrive if there is none. At the end of the stream, this returns zero.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn peek_message_len(fd: BorrowedFd<'_>) -> io::Result<usize> {
+    // With `MSG_TRUNC`, Linux returns the full length of the message, even
+    // though the buffer is empty.
+    let (_, len) = rustix::net::recv(fd, &mut [0_u8; 0], RecvFlags::PEEK | RecvFlags::TRUNC)?;
+    Ok(len)
+}
+
+/// Return the length of the next message in the queue, waiting for one to
+/// arrive if there is none. At the end of the stream, this returns zero.
+#[cfg(not(any(target_os = "android", target_os = "linux")))]
+fn peek_message_len(fd: BorrowedFd<'_>) -> io::Result<usize> {
+    // Other platforms don't support `MSG_TRUNC` as a `recv` flag, so peek
+    // with a growing buffer until the message fits.
+    let mut buf = vec![0_u8; 4096];
+    loop {
+        let (n, truncated) = recv_msg(fd, &mut [IoSliceMut::new(&mut buf)], RecvFlags::PEEK)?;
+        if !truncated {
+            return Ok(n);
+        }
+        buf.resize(buf.len() * 2, 0);
+    }
+}
+
+impl AsRawFd for SocketpairSeqpacket {
+    #[inline]
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+impl AsFd for SocketpairSeqpacket {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+impl IntoRawFd for SocketpairSeqpacket {
+    #[inline]
+    fn into_raw_fd(self) -> RawFd {
+        self.0.into_raw_fd()
+    }
+}
+
+impl From<SocketpairSeqpacket> for OwnedFd {
+    #[inline]
+    fn from(seqpacket: SocketpairSeqpacket) -> OwnedFd {
+        seqpacket.0
+    }
+}
+
+impl FromRawFd for SocketpairSeqpacket {
+    #[inline]
+    unsafe fn from_raw_fd(raw_fd: RawFd) -> Self {
+        Self(OwnedFd::from_raw_fd(raw_fd))
+    }
+}
+
+impl From<OwnedFd> for SocketpairSeqpacket {
+    #[inline]
+    fn from(fd: OwnedFd) -> Self {
+        Self(fd)
+    }
+}
+
+impl Debug for SocketpairSeqpacket {
+    #[allow(clippy::missing_inline_in_public_items)]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Just print the fd numbers; don't try to print the path or any
+        // information about it, because this information is otherwise
+        // unavailable to safe Rust code.
+        f.debug_struct("SocketpairSeqpacket")
+            .field("raw_fd", &self.0.as_raw_fd())
+            .finish()
+    }
+}
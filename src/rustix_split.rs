@@ -0,0 +1,204 @@
+//! `SocketpairReadHalf` and `SocketpairWriteHalf` for Posix-ish platforms.
+
+use crate::rustix::SocketpairStream;
+use io_extras::os::rustix::{AsRawFd, RawFd};
+use io_lifetimes::{AsFd, BorrowedFd};
+use std::error::Error;
+use std::fmt::{self, Debug, Display};
+use std::io::{self, IoSlice, IoSliceMut, Read, Write};
+use std::net::Shutdown;
+use std::sync::Arc;
+
+/// The read half of a [`SocketpairStream`], created by
+/// [`SocketpairStream::into_split`].
+pub struct SocketpairReadHalf(Arc<SocketpairStream>);
+
+/// The write half of a [`SocketpairStream`], created by
+/// [`SocketpairStream::into_split`].
+///
+/// Dropping the write half shuts down the write direction of the stream, so
+/// the peer sees an end-of-stream.
+pub struct SocketpairWriteHalf {
+    stream: Arc<SocketpairStream>,
+    shutdown_on_drop: bool,
+}
+
+/// Split `stream` into owned read and write halves.
+pub(crate) fn split(stream: SocketpairStream) -> (SocketpairReadHalf, SocketpairWriteHalf) {
+    let stream = Arc::new(stream);
+    (
+        SocketpairReadHalf(Arc::clone(&stream)),
+        SocketpairWriteHalf {
+            stream,
+            shutdown_on_drop: true,
+        },
+    )
+}
+
+/// Reunite `read` and `write` into the stream they were split from.
+fn reunite(
+    read: SocketpairReadHalf,
+    mut write: SocketpairWriteHalf,
+) -> Result<SocketpairStream, ReuniteError> {
+    if !Arc::ptr_eq(&read.0, &write.stream) {
+        return Err(ReuniteError(read, write));
+    }
+
+    write.shutdown_on_drop = false;
+    let stream = Arc::clone(&write.stream);
+    drop(read);
+    drop(write);
+    Ok(Arc::try_unwrap(stream).expect("SocketpairStream: try_unwrap failed in reunite"))
+}
+
+impl SocketpairReadHalf {
+    /// Reunite with a [`SocketpairWriteHalf`] to recover the original
+    /// [`SocketpairStream`].
+    ///
+    /// This fails if the halves came from different streams.
+    #[inline]
+    pub fn reunite(self, other: SocketpairWriteHalf) -> Result<SocketpairStream, ReuniteError> {
+        reunite(self, other)
+    }
+
+    /// Receives data on the socket from the remote address to which it is
+    /// connected, without removing that data from the queue. On success,
+    /// returns the number of bytes peeked.
+    #[inline]
+    pub fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.peek(buf)
+    }
+
+    /// Return the number of bytes which are ready to be read immediately.
+    #[inline]
+    pub fn num_ready_bytes(&self) -> io::Result<u64> {
+        self.0.num_ready_bytes()
+    }
+}
+
+impl SocketpairWriteHalf {
+    /// Reunite with a [`SocketpairReadHalf`] to recover the original
+    /// [`SocketpairStream`].
+    ///
+    /// This fails if the halves came from different streams.
+    #[inline]
+    pub fn reunite(self, other: SocketpairReadHalf) -> Result<SocketpairStream, ReuniteError> {
+        reunite(other, self)
+    }
+}
+
+impl Drop for SocketpairWriteHalf {
+    #[inline]
+    fn drop(&mut self) {
+        if self.shutdown_on_drop {
+            let _ = self.stream.shutdown(Shutdown::Write);
+        }
+    }
+}
+
+impl Read for SocketpairReadHalf {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&*self.0).read(buf)
+    }
+
+    #[inline]
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut]) -> io::Result<usize> {
+        (&*self.0).read_vectored(bufs)
+    }
+
+    #[cfg(can_vector)]
+    #[inline]
+    fn is_read_vectored(&self) -> bool {
+        (&*self.0).is_read_vectored()
+    }
+}
+
+impl Write for SocketpairWriteHalf {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&*self.stream).write(buf)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        (&*self.stream).flush()
+    }
+
+    #[inline]
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> io::Result<usize> {
+        (&*self.stream).write_vectored(bufs)
+    }
+
+    #[cfg(can_vector)]
+    #[inline]
+    fn is_write_vectored(&self) -> bool {
+        (&*self.stream).is_write_vectored()
+    }
+}
+
+impl AsRawFd for SocketpairReadHalf {
+    #[inline]
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+impl AsFd for SocketpairReadHalf {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+impl AsRawFd for SocketpairWriteHalf {
+    #[inline]
+    fn as_raw_fd(&self) -> RawFd {
+        self.stream.as_raw_fd()
+    }
+}
+
+impl AsFd for SocketpairWriteHalf {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.stream.as_fd()
+    }
+}
+
+impl Debug for SocketpairReadHalf {
+    #[allow(clippy::missing_inline_in_public_items)]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Just print the fd numbers; don't try to print the path or any
+        // information about it, because this information is otherwise
+        // unavailable to safe Rust code.
+        f.debug_struct("SocketpairReadHalf")
+            .field("raw_fd", &self.0.as_raw_fd())
+            .finish()
+    }
+}
+
+impl Debug for SocketpairWriteHalf {
+    #[allow(clippy::missing_inline_in_public_items)]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Just print the fd numbers; don't try to print the path or any
+        // information about it, because this information is otherwise
+        // unavailable to safe Rust code.
+        f.debug_struct("SocketpairWriteHalf")
+            .field("raw_fd", &self.stream.as_raw_fd())
+            .finish()
+    }
+}
+
+/// The error returned when trying to reunite halves which came from
+/// different streams. It contains the two halves, unchanged.
+#[derive(Debug)]
+pub struct ReuniteError(pub SocketpairReadHalf, pub SocketpairWriteHalf);
+
+impl Display for ReuniteError {
+    #[allow(clippy::missing_inline_in_public_items)]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("tried to reunite halves that are not from the same socket")
+    }
+}
+
+impl Error for ReuniteError {}
@@ -1,94 +1,190 @@
-//! `AsyncStdSocketpairStream` and `async_std_socketpair_stream` for Unix
-//! platforms.
+//! `AsyncStdSocketpairStream`, `async_std_socketpair_stream`,
+//! `AsyncStdSocketpairSeqpacket`, and `async_std_socketpair_seqpacket` for
+//! Unix platforms.
 
+#[cfg(any(target_os = "android", target_os = "linux"))]
+use crate::UCred;
+use crate::{
+    socketpair_seqpacket_messages, socketpair_stream, SocketpairSeqpacket, SocketpairStream,
+};
+use async_io::Async;
 use async_std::io::{self, IoSlice, IoSliceMut, Read, Write};
-use async_std::os::unix::net::UnixStream;
 use io_extras::os::rustix::{
     AsRawFd, AsRawReadWriteFd, AsReadWriteFd, FromRawFd, IntoRawFd, RawFd,
 };
 use io_lifetimes::{AsFd, BorrowedFd, OwnedFd};
 use std::fmt::{self, Debug};
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 
 /// A socketpair stream, which is a bidirectional bytestream much like a
 /// [`UnixStream`] except that it does not have a name or address.
+///
+/// [`UnixStream`]: async_std::os::unix::net::UnixStream
 #[repr(transparent)]
 #[derive(Clone)]
-pub struct AsyncStdSocketpairStream(UnixStream);
+pub struct AsyncStdSocketpairStream(Arc<Async<SocketpairStream>>);
 
 impl AsyncStdSocketpairStream {
-    // No `peek` in `async_std`'s `UnixStream` yet.
-    /*
+    /// Creates a new `AsyncStdSocketpairStream` from a [`SocketpairStream`],
+    /// putting it in nonblocking mode.
+    #[inline]
+    pub fn from_std(stream: SocketpairStream) -> io::Result<Self> {
+        Ok(Self(Arc::new(Async::new(stream)?)))
+    }
+
+    /// Turns this `AsyncStdSocketpairStream` into a [`SocketpairStream`],
+    /// putting it back in blocking mode.
+    ///
+    /// Like `async_std`'s conversion from `UnixStream` to
+    /// [`std::os::unix::net::UnixStream`], this fails if other clones of this
+    /// stream still exist, because they share the nonblocking mode.
+    pub fn into_std(self) -> io::Result<SocketpairStream> {
+        let stream = Arc::try_unwrap(self.0)
+            .map_err(|_| {
+                io::Error::other(
+                    "cannot convert AsyncStdSocketpairStream to SocketpairStream while it is still in use",
+                )
+            })?
+            .into_inner()?;
+        rustix::io::ioctl_fionbio(&stream, false)?;
+        Ok(stream)
+    }
+
     /// Receives data on the socket from the remote address to which it is
     /// connected, without removing that data from the queue. On success,
     /// returns the number of bytes peeked.
+    ///
+    /// This waits until data is available, like a read would.
     #[inline]
-    pub fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
-        self.0.peek(buf)
+    pub async fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
+        // `async_std`'s `UnixStream` has no `peek`, so we register the socket
+        // with `async_io`, which is the reactor `async_std` uses, ourselves.
+        self.0.read_with(|inner| inner.peek(buf)).await
     }
-    */
 
     /// Return the number of bytes which are ready to be read immediately.
     #[inline]
     pub fn num_ready_bytes(&self) -> io::Result<u64> {
         Ok(rustix::io::ioctl_fionread(self)?)
     }
+
+    /// Return the credentials of the process which created the other end of
+    /// the socketpair, using `SO_PEERCRED`.
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    #[inline]
+    pub fn peer_credentials(&self) -> io::Result<UCred> {
+        crate::rustix::peer_credentials(self.as_fd())
+    }
 }
 
 /// Create a socketpair and return stream handles connected to each end.
 #[inline]
 pub async fn async_std_socketpair_stream(
 ) -> io::Result<(AsyncStdSocketpairStream, AsyncStdSocketpairStream)> {
-    UnixStream::pair().map(|(a, b)| (AsyncStdSocketpairStream(a), AsyncStdSocketpairStream(b)))
+    let (a, b) = socketpair_stream()?;
+    Ok((
+        AsyncStdSocketpairStream::from_std(a)?,
+        AsyncStdSocketpairStream::from_std(b)?,
+    ))
+}
+
+/// A socketpair seqpacket, which is a bidirectional reliable message channel
+/// with an async message-oriented API much like [`SocketpairSeqpacket`].
+#[derive(Clone)]
+pub struct AsyncStdSocketpairSeqpacket(Arc<Async<SocketpairSeqpacket>>);
+
+impl AsyncStdSocketpairSeqpacket {
+    /// Sends `buf` as a single message. On success, returns the number of
+    /// bytes sent.
+    #[inline]
+    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write_with(|inner| inner.send(buf)).await
+    }
+
+    /// Receives a single message into `buf`. On success, returns the number
+    /// of bytes received and whether the message was truncated because it
+    /// didn't fit in `buf`.
+    ///
+    /// A return value of `(0, false)` means either an empty message was
+    /// received or the peer has closed the connection.
+    #[inline]
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<(usize, bool)> {
+        self.0.read_with(|inner| inner.recv(buf)).await
+    }
+
+    /// Return the number of bytes which are ready to be read immediately.
+    ///
+    /// This is the total over all queued messages, not the length of the
+    /// next message.
+    #[inline]
+    pub fn num_ready_bytes(&self) -> io::Result<u64> {
+        self.0.get_ref().num_ready_bytes()
+    }
+}
+
+/// Create a socketpair and return seqpacket handles connected to each end.
+///
+/// This uses the same socket setup as [`socketpair_seqpacket`].
+///
+/// [`socketpair_seqpacket`]: crate::socketpair_seqpacket
+#[inline]
+pub async fn async_std_socketpair_seqpacket(
+) -> io::Result<(AsyncStdSocketpairSeqpacket, AsyncStdSocketpairSeqpacket)> {
+    let (a, b) = socketpair_seqpacket_messages()?;
+    Ok((
+        AsyncStdSocketpairSeqpacket(Arc::new(Async::new(a)?)),
+        AsyncStdSocketpairSeqpacket(Arc::new(Async::new(b)?)),
+    ))
 }
 
 impl Read for AsyncStdSocketpairStream {
     #[inline]
     fn poll_read(
-        mut self: Pin<&mut Self>,
+        self: Pin<&mut Self>,
         cx: &mut Context<'_>,
         buf: &mut [u8],
     ) -> Poll<io::Result<usize>> {
-        Pin::new(&mut self.0).poll_read(cx, buf)
+        Pin::new(&mut &*self.0).poll_read(cx, buf)
     }
 
     #[inline]
     fn poll_read_vectored(
-        mut self: Pin<&mut Self>,
+        self: Pin<&mut Self>,
         cx: &mut Context<'_>,
         bufs: &mut [IoSliceMut<'_>],
     ) -> Poll<io::Result<usize>> {
-        Pin::new(&mut self.0).poll_read_vectored(cx, bufs)
+        Pin::new(&mut &*self.0).poll_read_vectored(cx, bufs)
     }
 }
 
 impl Write for AsyncStdSocketpairStream {
     #[inline]
     fn poll_write(
-        mut self: Pin<&mut Self>,
+        self: Pin<&mut Self>,
         cx: &mut Context<'_>,
         buf: &[u8],
     ) -> Poll<io::Result<usize>> {
-        Pin::new(&mut self.0).poll_write(cx, buf)
+        Pin::new(&mut &*self.0).poll_write(cx, buf)
     }
 
     fn poll_write_vectored(
-        mut self: Pin<&mut Self>,
+        self: Pin<&mut Self>,
         cx: &mut Context<'_>,
         bufs: &[IoSlice<'_>],
     ) -> Poll<io::Result<usize>> {
-        Pin::new(&mut self.0).poll_write_vectored(cx, bufs)
+        Pin::new(&mut &*self.0).poll_write_vectored(cx, bufs)
     }
 
     #[inline]
-    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
-        Pin::new(&mut self.0).poll_flush(cx)
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut &*self.0).poll_flush(cx)
     }
 
     #[inline]
-    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
-        Pin::new(&mut self.0).poll_close(cx)
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut &*self.0).poll_close(cx)
     }
 }
 
@@ -109,28 +205,30 @@ impl AsFd for AsyncStdSocketpairStream {
 impl IntoRawFd for AsyncStdSocketpairStream {
     #[inline]
     fn into_raw_fd(self) -> RawFd {
-        self.0.into_raw_fd()
+        OwnedFd::from(self).into_raw_fd()
     }
 }
 
 impl From<AsyncStdSocketpairStream> for OwnedFd {
     #[inline]
     fn from(stream: AsyncStdSocketpairStream) -> OwnedFd {
-        stream.0.into()
+        // Other clones may still be using the socket, so, like `async_std`,
+        // return a new handle to it.
+        stream.0.get_ref().try_clone().unwrap().into()
     }
 }
 
 impl FromRawFd for AsyncStdSocketpairStream {
     #[inline]
     unsafe fn from_raw_fd(raw_fd: RawFd) -> Self {
-        Self(UnixStream::from_raw_fd(raw_fd))
+        Self::from(OwnedFd::from_raw_fd(raw_fd))
     }
 }
 
 impl From<OwnedFd> for AsyncStdSocketpairStream {
     #[inline]
     fn from(fd: OwnedFd) -> Self {
-        Self(UnixStream::from(fd))
+        Self::from_std(SocketpairStream::from(fd)).expect("SocketpairStream is known to be good")
     }
 }
 
@@ -169,3 +267,29 @@ impl Debug for AsyncStdSocketpairStream {
             .finish()
     }
 }
+
+impl AsRawFd for AsyncStdSocketpairSeqpacket {
+    #[inline]
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+impl AsFd for AsyncStdSocketpairSeqpacket {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+impl Debug for AsyncStdSocketpairSeqpacket {
+    #[allow(clippy::missing_inline_in_public_items)]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Just print the fd numbers; don't try to print the path or any
+        // information about it, because this information is otherwise
+        // unavailable to safe Rust code.
+        f.debug_struct("AsyncStdSocketpairSeqpacket")
+            .field("raw_fd", &self.0.as_raw_fd())
+            .finish()
+    }
+}
@@ -0,0 +1,195 @@
+//! `FuturesSocketpairStream` and `futures_socketpair_stream` for Unix
+//! platforms.
+
+use crate::{socketpair_stream, SmolSocketpairStream, SocketpairStream};
+use futures_io::{AsyncBufRead, AsyncRead, AsyncWrite};
+use io_extras::os::rustix::{AsRawFd, AsRawReadWriteFd, AsReadWriteFd, RawFd};
+use io_lifetimes::{AsFd, BorrowedFd};
+use std::fmt::{self, Debug};
+use std::io::{self, IoSlice, IoSliceMut, Read};
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+/// The size of the read buffer used for `AsyncBufRead`.
+const BUF_SIZE: usize = 8 * 1024;
+
+/// A socketpair stream, which is a bidirectional bytestream much like a
+/// [`UnixStream`] except that it does not have a name or address.
+///
+/// This implements the `futures-io` traits, so it can be used with any
+/// executor. It is a [`SmolSocketpairStream`] with a read buffer, to support
+/// `AsyncBufRead`, so it waits for readiness using `async_io`, which runs its
+/// own reactor thread when no `async_io`-based runtime is driving it.
+///
+/// [`UnixStream`]: std::os::unix::net::UnixStream
+pub struct FuturesSocketpairStream {
+    inner: SmolSocketpairStream,
+    buf: Box<[u8]>,
+    pos: usize,
+    filled: usize,
+}
+
+impl FuturesSocketpairStream {
+    /// Creates a new `FuturesSocketpairStream` from a [`SocketpairStream`],
+    /// putting it in nonblocking mode.
+    #[inline]
+    pub fn from_std(stream: SocketpairStream) -> io::Result<Self> {
+        Ok(Self::from_smol(SmolSocketpairStream::from_std(stream)?))
+    }
+
+    /// Creates a new `FuturesSocketpairStream` from a
+    /// [`SmolSocketpairStream`], adding a read buffer.
+    #[inline]
+    pub fn from_smol(stream: SmolSocketpairStream) -> Self {
+        Self {
+            inner: stream,
+            buf: vec![0; BUF_SIZE].into_boxed_slice(),
+            pos: 0,
+            filled: 0,
+        }
+    }
+
+    /// Return the contents of the read buffer, which have been received from
+    /// the socket but not yet consumed.
+    #[inline]
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf[self.pos..self.filled]
+    }
+}
+
+/// Create a socketpair and return stream handles connected to each end.
+#[inline]
+pub fn futures_socketpair_stream() -> io::Result<(FuturesSocketpairStream, FuturesSocketpairStream)>
+{
+    let (a, b) = socketpair_stream()?;
+    Ok((
+        FuturesSocketpairStream::from_std(a)?,
+        FuturesSocketpairStream::from_std(b)?,
+    ))
+}
+
+impl AsyncRead for FuturesSocketpairStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        // If the buffer is empty and the read is at least as large as the
+        // buffer, bypass the buffer.
+        if self.pos == self.filled && buf.len() >= self.buf.len() {
+            return Pin::new(&mut &self.inner).poll_read(cx, buf);
+        }
+        let n = ready!(self.as_mut().poll_fill_buf(cx))?.read(buf)?;
+        self.consume(n);
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_read_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &mut [IoSliceMut<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let len = bufs.iter().map(|buf| buf.len()).sum::<usize>();
+        if self.pos == self.filled && len >= self.buf.len() {
+            return Pin::new(&mut &self.inner).poll_read_vectored(cx, bufs);
+        }
+        let n = ready!(self.as_mut().poll_fill_buf(cx))?.read_vectored(bufs)?;
+        self.consume(n);
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl AsyncBufRead for FuturesSocketpairStream {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        if this.pos == this.filled {
+            this.filled = ready!(Pin::new(&mut &this.inner).poll_read(cx, &mut this.buf))?;
+            this.pos = 0;
+        }
+        Poll::Ready(Ok(&this.buf[this.pos..this.filled]))
+    }
+
+    #[inline]
+    fn consume(mut self: Pin<&mut Self>, amt: usize) {
+        self.pos = std::cmp::min(self.pos + amt, self.filled);
+    }
+}
+
+impl AsyncWrite for FuturesSocketpairStream {
+    #[inline]
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &self.inner).poll_write_vectored(cx, bufs)
+    }
+
+    #[inline]
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut &self.inner).poll_flush(cx)
+    }
+
+    #[inline]
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut &self.inner).poll_close(cx)
+    }
+}
+
+impl AsRawFd for FuturesSocketpairStream {
+    #[inline]
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+impl AsFd for FuturesSocketpairStream {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inner.as_fd()
+    }
+}
+
+impl AsRawReadWriteFd for FuturesSocketpairStream {
+    #[inline]
+    fn as_raw_read_fd(&self) -> RawFd {
+        self.as_raw_fd()
+    }
+
+    #[inline]
+    fn as_raw_write_fd(&self) -> RawFd {
+        self.as_raw_fd()
+    }
+}
+
+impl AsReadWriteFd for FuturesSocketpairStream {
+    #[inline]
+    fn as_read_fd(&self) -> BorrowedFd<'_> {
+        self.as_fd()
+    }
+
+    #[inline]
+    fn as_write_fd(&self) -> BorrowedFd<'_> {
+        self.as_fd()
+    }
+}
+
+impl Debug for FuturesSocketpairStream {
+    #[allow(clippy::missing_inline_in_public_items)]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Just print the fd numbers; don't try to print the path or any
+        // information about it, because this information is otherwise
+        // unavailable to safe Rust code.
+        f.debug_struct("FuturesSocketpairStream")
+            .field("raw_fd", &self.inner.as_raw_fd())
+            .finish()
+    }
+}
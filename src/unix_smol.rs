@@ -0,0 +1,254 @@
+//! `SmolSocketpairStream` and `smol_socketpair_stream` for Unix platforms.
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+use crate::UCred;
+use crate::{socketpair_stream, SocketpairStream};
+use async_io::Async;
+use futures_io::{AsyncRead, AsyncWrite};
+use io_extras::os::rustix::{
+    AsRawFd, AsRawReadWriteFd, AsReadWriteFd, FromRawFd, IntoRawFd, RawFd,
+};
+use io_lifetimes::{AsFd, BorrowedFd, OwnedFd};
+use std::fmt::{self, Debug};
+use std::io::{self, IoSlice, IoSliceMut};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A socketpair stream, which is a bidirectional bytestream much like a
+/// [`UnixStream`] except that it does not have a name or address.
+///
+/// This uses `async_io`, the reactor behind `smol`.
+///
+/// [`UnixStream`]: std::os::unix::net::UnixStream
+#[repr(transparent)]
+pub struct SmolSocketpairStream(Async<SocketpairStream>);
+
+impl SmolSocketpairStream {
+    /// Creates a new `SmolSocketpairStream` from a [`SocketpairStream`],
+    /// putting it in nonblocking mode.
+    #[inline]
+    pub fn from_std(stream: SocketpairStream) -> io::Result<Self> {
+        Async::new(stream).map(Self)
+    }
+
+    /// Turns this `SmolSocketpairStream` into a [`SocketpairStream`], putting
+    /// it back in blocking mode.
+    pub fn into_std(self) -> io::Result<SocketpairStream> {
+        let stream = self.0.into_inner()?;
+        rustix::io::ioctl_fionbio(&stream, false)?;
+        Ok(stream)
+    }
+
+    /// Receives data on the socket from the remote address to which it is
+    /// connected, without removing that data from the queue. On success,
+    /// returns the number of bytes peeked.
+    ///
+    /// This waits until data is available, like a read would.
+    #[inline]
+    pub async fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read_with(|inner| inner.peek(buf)).await
+    }
+
+    /// Return the number of bytes which are ready to be read immediately.
+    #[inline]
+    pub fn num_ready_bytes(&self) -> io::Result<u64> {
+        Ok(rustix::io::ioctl_fionread(self)?)
+    }
+
+    /// Return the credentials of the process which created the other end of
+    /// the socketpair, using `SO_PEERCRED`.
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    #[inline]
+    pub fn peer_credentials(&self) -> io::Result<UCred> {
+        crate::rustix::peer_credentials(self.as_fd())
+    }
+}
+
+/// Create a socketpair and return stream handles connected to each end.
+#[inline]
+pub fn smol_socketpair_stream() -> io::Result<(SmolSocketpairStream, SmolSocketpairStream)> {
+    let (a, b) = socketpair_stream()?;
+    Ok((
+        SmolSocketpairStream::from_std(a)?,
+        SmolSocketpairStream::from_std(b)?,
+    ))
+}
+
+impl AsyncRead for SmolSocketpairStream {
+    #[inline]
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &self.0).poll_read(cx, buf)
+    }
+
+    #[inline]
+    fn poll_read_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &mut [IoSliceMut<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &self.0).poll_read_vectored(cx, bufs)
+    }
+}
+
+impl AsyncRead for &SmolSocketpairStream {
+    #[inline]
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &self.0).poll_read(cx, buf)
+    }
+
+    #[inline]
+    fn poll_read_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &mut [IoSliceMut<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &self.0).poll_read_vectored(cx, bufs)
+    }
+}
+
+impl AsyncWrite for SmolSocketpairStream {
+    #[inline]
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &self.0).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &self.0).poll_write_vectored(cx, bufs)
+    }
+
+    #[inline]
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut &self.0).poll_flush(cx)
+    }
+
+    #[inline]
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut &self.0).poll_close(cx)
+    }
+}
+
+impl AsyncWrite for &SmolSocketpairStream {
+    #[inline]
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &self.0).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut &self.0).poll_write_vectored(cx, bufs)
+    }
+
+    #[inline]
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut &self.0).poll_flush(cx)
+    }
+
+    #[inline]
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut &self.0).poll_close(cx)
+    }
+}
+
+impl AsRawFd for SmolSocketpairStream {
+    #[inline]
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+impl AsFd for SmolSocketpairStream {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+impl IntoRawFd for SmolSocketpairStream {
+    #[inline]
+    fn into_raw_fd(self) -> RawFd {
+        OwnedFd::from(self).into_raw_fd()
+    }
+}
+
+impl From<SmolSocketpairStream> for OwnedFd {
+    #[inline]
+    fn from(stream: SmolSocketpairStream) -> OwnedFd {
+        stream
+            .into_std()
+            .expect("failed to deregister SmolSocketpairStream")
+            .into()
+    }
+}
+
+impl FromRawFd for SmolSocketpairStream {
+    #[inline]
+    unsafe fn from_raw_fd(raw_fd: RawFd) -> Self {
+        Self::from(OwnedFd::from_raw_fd(raw_fd))
+    }
+}
+
+impl From<OwnedFd> for SmolSocketpairStream {
+    #[inline]
+    fn from(fd: OwnedFd) -> Self {
+        Self::from_std(SocketpairStream::from(fd)).expect("SocketpairStream is known to be good")
+    }
+}
+
+impl AsRawReadWriteFd for SmolSocketpairStream {
+    #[inline]
+    fn as_raw_read_fd(&self) -> RawFd {
+        self.as_raw_fd()
+    }
+
+    #[inline]
+    fn as_raw_write_fd(&self) -> RawFd {
+        self.as_raw_fd()
+    }
+}
+
+impl AsReadWriteFd for SmolSocketpairStream {
+    #[inline]
+    fn as_read_fd(&self) -> BorrowedFd<'_> {
+        self.as_fd()
+    }
+
+    #[inline]
+    fn as_write_fd(&self) -> BorrowedFd<'_> {
+        self.as_fd()
+    }
+}
+
+impl Debug for SmolSocketpairStream {
+    #[allow(clippy::missing_inline_in_public_items)]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Just print the fd numbers; don't try to print the path or any
+        // information about it, because this information is otherwise
+        // unavailable to safe Rust code.
+        f.debug_struct("SmolSocketpairStream")
+            .field("raw_fd", &self.0.as_raw_fd())
+            .finish()
+    }
+}
@@ -1,12 +1,24 @@
-//! `TokioSocketpairStream` and `tokio_socketpair_stream` for Unix platforms.
+//! `TokioSocketpairStream`, `tokio_socketpair_stream`,
+//! `TokioSocketpairSeqpacket`, and `tokio_socketpair_seqpacket` for Unix
+//! platforms.
 
-use io_extras::os::rustix::{AsRawFd, AsRawReadWriteFd, AsReadWriteFd, RawFd};
-use io_lifetimes::{AsFd, BorrowedFd};
+#[cfg(any(target_os = "android", target_os = "linux"))]
+use crate::UCred;
+use crate::{
+    SocketpairBuilder, SocketpairEndOptions, SocketpairKind, SocketpairSeqpacket, SocketpairStream,
+};
+use io_extras::os::rustix::{
+    AsRawFd, AsRawReadWriteFd, AsReadWriteFd, FromRawFd, IntoRawFd, RawFd,
+};
+use io_lifetimes::{AsFd, BorrowedFd, OwnedFd};
+use rustix::net::RecvFlags;
 use std::fmt::{self, Debug};
+use std::future::poll_fn;
 use std::io::IoSlice;
 use std::pin::Pin;
-use std::task::{Context, Poll};
-use tokio::io::{self, AsyncRead, AsyncWrite, ReadBuf};
+use std::task::{ready, Context, Poll};
+use tokio::io::unix::AsyncFd;
+use tokio::io::{self, AsyncRead, AsyncWrite, Interest, ReadBuf};
 use tokio::net::UnixStream;
 
 /// A socketpair stream, which is a bidirectional bytestream much like a
@@ -15,22 +27,116 @@ use tokio::net::UnixStream;
 pub struct TokioSocketpairStream(UnixStream);
 
 impl TokioSocketpairStream {
-    // No `peek` in `tokio`'s `UnixStream` yet.
-    /*
+    /// Creates a new `TokioSocketpairStream` from a [`SocketpairStream`],
+    /// putting it in nonblocking mode.
+    ///
+    /// This must be called from within a `tokio` runtime.
+    pub fn from_std(stream: SocketpairStream) -> io::Result<Self> {
+        let stream = std::os::unix::net::UnixStream::from(OwnedFd::from(stream));
+        stream.set_nonblocking(true)?;
+        UnixStream::from_std(stream).map(Self)
+    }
+
+    /// Turns this `TokioSocketpairStream` into a [`SocketpairStream`], putting
+    /// it back in blocking mode.
+    pub fn into_std(self) -> io::Result<SocketpairStream> {
+        let stream = self.0.into_std()?;
+        stream.set_nonblocking(false)?;
+        Ok(SocketpairStream::from(OwnedFd::from(stream)))
+    }
+
     /// Receives data on the socket from the remote address to which it is
     /// connected, without removing that data from the queue. On success,
     /// returns the number of bytes peeked.
+    ///
+    /// This waits until data is available, like a read would.
     #[inline]
-    pub fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
-        self.0.peek(buf)
+    pub async fn peek(&self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut buf = ReadBuf::new(buf);
+        poll_fn(|cx| self.poll_peek(cx, &mut buf)).await
+    }
+
+    /// Attempts to receive data on the socket, without removing that data
+    /// from the queue, registering the current task for wakeup if data is
+    /// not yet available. On success, the peeked data is appended to the
+    /// filled part of `buf`, and the number of bytes peeked is returned.
+    pub fn poll_peek(
+        &self,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<usize>> {
+        // `tokio`'s `UnixStream` has no `peek`, so use its readiness with
+        // `MSG_PEEK` ourselves.
+        loop {
+            ready!(self.0.poll_read_ready(cx))?;
+            let result = self.0.try_io(Interest::READABLE, || {
+                let (n, _) =
+                    rustix::net::recv(&self.0, buf.initialize_unfilled(), RecvFlags::PEEK)?;
+                Ok(n)
+            });
+            match result {
+                Ok(n) => {
+                    buf.advance(n);
+                    return Poll::Ready(Ok(n));
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => continue,
+                Err(err) => return Poll::Ready(Err(err)),
+            }
+        }
     }
-    */
 
     /// Return the number of bytes which are ready to be read immediately.
     #[inline]
     pub fn num_ready_bytes(&self) -> io::Result<u64> {
         Ok(rustix::io::ioctl_fionread(self)?)
     }
+
+    /// Return the credentials of the process which created the other end of
+    /// the socketpair, using `SO_PEERCRED`.
+    #[cfg(any(target_os = "android", target_os = "linux"))]
+    #[inline]
+    pub fn peer_credentials(&self) -> io::Result<UCred> {
+        crate::rustix::peer_credentials(self.as_fd())
+    }
+
+    /// Sends `buf` along with the file descriptors in `fds`, using
+    /// `SCM_RIGHTS`. On success, returns the number of bytes sent.
+    ///
+    /// This has the same semantics as [`SocketpairStream::send_with_fds`],
+    /// except that it waits until the socket is writable.
+    ///
+    /// [`SocketpairStream::send_with_fds`]: crate::SocketpairStream::send_with_fds
+    pub async fn send_with_fds(&self, buf: &[u8], fds: &[BorrowedFd<'_>]) -> io::Result<usize> {
+        loop {
+            self.0.writable().await?;
+            match self.0.try_io(Interest::WRITABLE, || {
+                crate::rustix::send_with_fds(self.as_fd(), buf, fds)
+            }) {
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => continue,
+                result => return result,
+            }
+        }
+    }
+
+    /// Receives data into `buf` along with any file descriptors sent with it
+    /// using `SCM_RIGHTS`, which are appended to `fds`. On success, returns
+    /// the number of bytes received.
+    ///
+    /// This has the same semantics as [`SocketpairStream::recv_with_fds`],
+    /// except that it waits until data is available.
+    ///
+    /// [`SocketpairStream::recv_with_fds`]: crate::SocketpairStream::recv_with_fds
+    pub async fn recv_with_fds(&self, buf: &mut [u8], fds: &mut Vec<OwnedFd>) -> io::Result<usize> {
+        loop {
+            self.0.readable().await?;
+            match self.0.try_io(Interest::READABLE, || {
+                crate::rustix::recv_with_fds(self.as_fd(), buf, fds)
+            }) {
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => continue,
+                result => return result,
+            }
+        }
+    }
 }
 
 /// Create a socketpair and return stream handles connected to each end.
@@ -40,6 +146,69 @@ pub async fn tokio_socketpair_stream() -> io::Result<(TokioSocketpairStream, Tok
     UnixStream::pair().map(|(a, b)| (TokioSocketpairStream(a), TokioSocketpairStream(b)))
 }
 
+/// A socketpair seqpacket, which is a bidirectional reliable message channel
+/// with an async message-oriented API much like [`SocketpairSeqpacket`].
+pub struct TokioSocketpairSeqpacket(AsyncFd<SocketpairSeqpacket>);
+
+impl TokioSocketpairSeqpacket {
+    /// Sends `buf` as a single message. On success, returns the number of
+    /// bytes sent.
+    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        loop {
+            let mut guard = self.0.writable().await?;
+            match guard.try_io(|inner| inner.get_ref().send(buf)) {
+                Ok(result) => return result,
+                Err(_would_block) => continue,
+            }
+        }
+    }
+
+    /// Receives a single message into `buf`. On success, returns the number
+    /// of bytes received and whether the message was truncated because it
+    /// didn't fit in `buf`.
+    ///
+    /// A return value of `(0, false)` means either an empty message was
+    /// received or the peer has closed the connection.
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<(usize, bool)> {
+        loop {
+            let mut guard = self.0.readable().await?;
+            match guard.try_io(|inner| inner.get_ref().recv(buf)) {
+                Ok(result) => return result,
+                Err(_would_block) => continue,
+            }
+        }
+    }
+
+    /// Return the number of bytes which are ready to be read immediately.
+    ///
+    /// This is the total over all queued messages, not the length of the
+    /// next message.
+    #[inline]
+    pub fn num_ready_bytes(&self) -> io::Result<u64> {
+        self.0.get_ref().num_ready_bytes()
+    }
+}
+
+/// Create a socketpair and return seqpacket handles connected to each end.
+///
+/// This uses the same socket setup as [`socketpair_seqpacket`], with both
+/// ends in nonblocking mode.
+///
+/// [`socketpair_seqpacket`]: crate::socketpair_seqpacket
+pub async fn tokio_socketpair_seqpacket(
+) -> io::Result<(TokioSocketpairSeqpacket, TokioSocketpairSeqpacket)> {
+    let (a, b) = SocketpairBuilder::new()
+        .kind(SocketpairKind::Seqpacket)
+        .ends(SocketpairEndOptions::new().nonblocking(true))
+        .build()?;
+    let a = SocketpairSeqpacket::from(OwnedFd::from(a));
+    let b = SocketpairSeqpacket::from(OwnedFd::from(b));
+    Ok((
+        TokioSocketpairSeqpacket(AsyncFd::new(a)?),
+        TokioSocketpairSeqpacket(AsyncFd::new(b)?),
+    ))
+}
+
 impl AsyncRead for TokioSocketpairStream {
     #[inline]
     fn poll_read(
@@ -94,6 +263,32 @@ impl AsFd for TokioSocketpairStream {
     }
 }
 
+impl IntoRawFd for TokioSocketpairStream {
+    #[inline]
+    fn into_raw_fd(self) -> RawFd {
+        self.into_std()
+            .expect("failed to deregister TokioSocketpairStream")
+            .into_raw_fd()
+    }
+}
+
+impl FromRawFd for TokioSocketpairStream {
+    #[inline]
+    unsafe fn from_raw_fd(raw_fd: RawFd) -> Self {
+        Self::from(OwnedFd::from_raw_fd(raw_fd))
+    }
+}
+
+impl From<OwnedFd> for TokioSocketpairStream {
+    /// Like [`UnixStream::from_std`], this panics if called outside of a
+    /// `tokio` runtime.
+    #[inline]
+    fn from(fd: OwnedFd) -> Self {
+        Self::from_std(SocketpairStream::from(fd))
+            .expect("failed to register TokioSocketpairStream")
+    }
+}
+
 impl AsRawReadWriteFd for TokioSocketpairStream {
     #[inline]
     fn as_raw_read_fd(&self) -> RawFd {
@@ -129,3 +324,29 @@ impl Debug for TokioSocketpairStream {
             .finish()
     }
 }
+
+impl AsRawFd for TokioSocketpairSeqpacket {
+    #[inline]
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+impl AsFd for TokioSocketpairSeqpacket {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.get_ref().as_fd()
+    }
+}
+
+impl Debug for TokioSocketpairSeqpacket {
+    #[allow(clippy::missing_inline_in_public_items)]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Just print the fd numbers; don't try to print the path or any
+        // information about it, because this information is otherwise
+        // unavailable to safe Rust code.
+        f.debug_struct("TokioSocketpairSeqpacket")
+            .field("raw_fd", &self.0.as_raw_fd())
+            .finish()
+    }
+}
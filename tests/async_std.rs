@@ -0,0 +1,106 @@
+#![cfg(all(unix, feature = "async-std"))]
+
+use async_std::io::{ReadExt, WriteExt};
+use async_std::task::{self, block_on};
+use socketpair::{
+    async_std_socketpair_seqpacket, async_std_socketpair_stream, socketpair_stream,
+    AsyncStdSocketpairStream,
+};
+use std::str;
+
+#[test]
+fn seqpacket() -> anyhow::Result<()> {
+    block_on(async {
+        let (a, b) = async_std_socketpair_seqpacket().await?;
+        let c = b.clone();
+
+        let t = task::spawn(async move {
+            let mut buf = [0_u8; 4096];
+            let (n, truncated) = b.recv(&mut buf).await?;
+            assert!(!truncated);
+            assert_eq!(str::from_utf8(&buf[..n]).unwrap(), "hello world");
+
+            b.send(b"greetings").await?;
+            Ok::<_, std::io::Error>(())
+        });
+
+        a.send(b"hello world").await?;
+
+        let mut buf = [0_u8; 4096];
+        let (n, truncated) = a.recv(&mut buf).await?;
+        assert!(!truncated);
+        assert_eq!(str::from_utf8(&buf[..n]).unwrap(), "greetings");
+        t.await?;
+
+        // A clone receives from the same socket.
+        a.send(b"goodbye").await?;
+        let mut buf = [0_u8; 4];
+        assert_eq!(c.recv(&mut buf).await?, (4, true));
+        assert_eq!(str::from_utf8(&buf).unwrap(), "good");
+
+        Ok(())
+    })
+}
+
+#[test]
+fn peek() -> anyhow::Result<()> {
+    block_on(async {
+        let (mut a, mut b) = async_std_socketpair_stream().await?;
+
+        // Start peeking before the data is written.
+        let t = task::spawn(async move {
+            let mut buf = vec![0_u8; 11];
+            assert_eq!(b.peek(&mut buf).await?, 11);
+            assert_eq!(str::from_utf8(&buf).unwrap(), "hello world");
+
+            // Peek with a buffer sized by `num_ready_bytes`.
+            let mut buf = vec![0_u8; b.num_ready_bytes()? as usize];
+            assert_eq!(buf.len(), 12);
+            assert_eq!(b.peek(&mut buf).await?, 12);
+            assert_eq!(str::from_utf8(&buf).unwrap(), "hello world\n");
+
+            let mut buf = String::new();
+            b.read_to_string(&mut buf).await?;
+            assert_eq!(buf, "hello world\n");
+            Ok::<_, std::io::Error>(())
+        });
+
+        task::yield_now().await;
+        a.write_all(b"hello world\n").await?;
+        drop(a);
+
+        t.await?;
+        Ok(())
+    })
+}
+
+#[test]
+fn std_conversions() -> anyhow::Result<()> {
+    block_on(async {
+        let (a, b) = socketpair_stream()?;
+        let mut a = AsyncStdSocketpairStream::from_std(a)?;
+        let mut b = AsyncStdSocketpairStream::from_std(b)?;
+
+        a.write_all(b"hello").await?;
+        let mut buf = [0_u8; 5];
+        b.read_exact(&mut buf).await?;
+        assert_eq!(&buf, b"hello");
+
+        // Converting fails while another clone exists.
+        let c = b.clone();
+        assert!(b.into_std().is_err());
+
+        // Back in blocking mode, a read waits for the writer thread.
+        let mut b = c.into_std()?;
+        let t = std::thread::spawn(move || -> std::io::Result<()> {
+            std::thread::sleep(std::time::Duration::from_millis(100));
+            let mut a = a.into_std()?;
+            std::io::Write::write_all(&mut a, b"world")
+        });
+        std::io::Read::read_exact(&mut b, &mut buf)?;
+        assert_eq!(&buf, b"world");
+        t.join().unwrap()?;
+
+        Ok(())
+    })
+}
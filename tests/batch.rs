@@ -0,0 +1,87 @@
+#![cfg(unix)]
+
+use socketpair::{socketpair_dgram, SocketpairSeqpacket};
+use std::{str, thread};
+
+#[test]
+fn seqpacket() -> anyhow::Result<()> {
+    let (a, b) = SocketpairSeqpacket::pair()?;
+
+    assert_eq!(a.send_batch(&[b"hello", b"", b"hello world"])?, 3);
+
+    let mut bufs = [[0_u8; 8]; 4];
+    let mut refs = bufs.iter_mut().map(|buf| &mut buf[..]).collect::<Vec<_>>();
+    // Only the three queued messages are received; the call doesn't wait
+    // for a fourth.
+    assert_eq!(
+        b.recv_batch(&mut refs)?,
+        [(5, false), (0, false), (8, true)]
+    );
+    assert_eq!(str::from_utf8(&bufs[0][..5]).unwrap(), "hello");
+    assert_eq!(str::from_utf8(&bufs[2]).unwrap(), "hello wo");
+
+    Ok(())
+}
+
+// On Darwin, `SocketpairSeqpacket` uses `SOCK_DGRAM`, which has no
+// end-of-stream.
+#[cfg(not(any(target_os = "ios", target_os = "macos")))]
+#[test]
+fn seqpacket_peer_closed() -> anyhow::Result<()> {
+    let (a, b) = SocketpairSeqpacket::pair()?;
+
+    assert_eq!(a.send_batch(&[b"one", b"", b"two"])?, 3);
+    drop(a);
+
+    let mut bufs = [[0_u8; 8]; 6];
+    let mut refs = bufs.iter_mut().map(|buf| &mut buf[..]).collect::<Vec<_>>();
+    // The unused buffers aren't reported as empty messages.
+    assert_eq!(
+        b.recv_batch(&mut refs)?,
+        [(3, false), (0, false), (3, false)]
+    );
+    assert_eq!(b.recv_batch(&mut refs)?, []);
+
+    Ok(())
+}
+
+#[test]
+fn dgram() -> anyhow::Result<()> {
+    let (a, b) = socketpair_dgram()?;
+
+    let msgs = (0..100)
+        .map(|i| format!("message {}", i))
+        .collect::<Vec<_>>();
+
+    // Send from another thread, in case the receive queue is short.
+    let sender = {
+        let a = a.try_clone()?;
+        let msgs = msgs.clone();
+        thread::spawn(move || -> std::io::Result<()> {
+            let msgs = msgs.iter().map(|msg| msg.as_bytes()).collect::<Vec<_>>();
+            let mut sent = 0;
+            while sent < msgs.len() {
+                sent += a.send_batch(&msgs[sent..])?;
+            }
+            Ok(())
+        })
+    };
+
+    let mut bufs = vec![[0_u8; 16]; 100];
+    let mut received = Vec::new();
+    while received.len() < msgs.len() {
+        let mut refs = bufs.iter_mut().map(|buf| &mut buf[..]).collect::<Vec<_>>();
+        let lens = b.recv_batch(&mut refs)?;
+        for (buf, (n, truncated)) in bufs.iter().zip(lens) {
+            assert!(!truncated);
+            received.push(String::from_utf8(buf[..n].to_vec())?);
+        }
+    }
+    sender.join().unwrap()?;
+    assert_eq!(received, msgs);
+
+    assert_eq!(a.send_batch(&[])?, 0);
+    assert_eq!(b.recv_batch(&mut [])?, []);
+
+    Ok(())
+}
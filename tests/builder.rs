@@ -0,0 +1,114 @@
+#![cfg(unix)]
+
+use rustix::io::FdFlags;
+use rustix::net::SocketType;
+use socketpair::{
+    socketpair_seqpacket, socketpair_stream, SocketpairBuilder, SocketpairEndOptions,
+    SocketpairKind,
+};
+use std::io::{self, Read, Write};
+use std::str;
+
+#[test]
+fn defaults() -> anyhow::Result<()> {
+    let (mut a, mut b) = SocketpairBuilder::new().build()?;
+
+    assert!(rustix::io::fcntl_getfd(&a)?.contains(FdFlags::CLOEXEC));
+    assert!(rustix::io::fcntl_getfd(&b)?.contains(FdFlags::CLOEXEC));
+    assert_eq!(rustix::net::sockopt::socket_type(&a)?, SocketType::STREAM);
+
+    writeln!(a, "hello world")?;
+    let mut buf = [0_u8; 4096];
+    let n = b.read(&mut buf)?;
+    assert_eq!(str::from_utf8(&buf[..n]).unwrap(), "hello world\n");
+
+    Ok(())
+}
+
+#[test]
+fn per_end() -> anyhow::Result<()> {
+    let (mut a, b) = SocketpairBuilder::new()
+        .first(SocketpairEndOptions::new().nonblocking(true))
+        .second(SocketpairEndOptions::new().cloexec(false))
+        .build()?;
+
+    assert!(rustix::io::fcntl_getfd(&a)?.contains(FdFlags::CLOEXEC));
+    assert!(!rustix::io::fcntl_getfd(&b)?.contains(FdFlags::CLOEXEC));
+
+    let mut buf = [0_u8; 16];
+    assert_eq!(
+        a.read(&mut buf).unwrap_err().kind(),
+        io::ErrorKind::WouldBlock
+    );
+
+    Ok(())
+}
+
+#[test]
+fn ends() -> anyhow::Result<()> {
+    let (mut a, mut b) = SocketpairBuilder::new()
+        .ends(SocketpairEndOptions::new().nonblocking(true))
+        .build()?;
+
+    let mut buf = [0_u8; 16];
+    assert_eq!(
+        a.read(&mut buf).unwrap_err().kind(),
+        io::ErrorKind::WouldBlock
+    );
+    assert_eq!(
+        b.read(&mut buf).unwrap_err().kind(),
+        io::ErrorKind::WouldBlock
+    );
+
+    Ok(())
+}
+
+#[test]
+fn buffer_sizes() -> anyhow::Result<()> {
+    let (a, b) = SocketpairBuilder::new()
+        .first(SocketpairEndOptions::new().send_buffer_size(0x10000))
+        .second(SocketpairEndOptions::new().recv_buffer_size(0x20000))
+        .build()?;
+
+    // Linux doubles the requested sizes to allow for bookkeeping overhead.
+    assert!(rustix::net::sockopt::socket_send_buffer_size(&a)? >= 0x10000);
+    assert!(rustix::net::sockopt::socket_recv_buffer_size(&b)? >= 0x20000);
+
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "ios", target_os = "macos")))]
+#[test]
+fn kinds() -> anyhow::Result<()> {
+    for (kind, type_) in [
+        (SocketpairKind::Stream, SocketType::STREAM),
+        (SocketpairKind::Seqpacket, SocketType::SEQPACKET),
+        (SocketpairKind::Datagram, SocketType::DGRAM),
+    ] {
+        let (a, b) = SocketpairBuilder::new().kind(kind).build()?;
+        assert_eq!(rustix::net::sockopt::socket_type(&a)?, type_);
+        assert_eq!(rustix::net::sockopt::socket_type(&b)?, type_);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn kind() -> anyhow::Result<()> {
+    let (a, _b) = socketpair_stream()?;
+    assert_eq!(a.kind()?, SocketpairKind::Stream);
+    assert!(format!("{:?}", a).contains("kind: Stream"));
+
+    let (a, _b) = socketpair_seqpacket()?;
+    #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+    assert_eq!(a.kind()?, SocketpairKind::Seqpacket);
+    #[cfg(any(target_os = "ios", target_os = "macos"))]
+    assert_eq!(a.kind()?, SocketpairKind::Datagram);
+
+    let (a, _b) = SocketpairBuilder::new()
+        .kind(SocketpairKind::Datagram)
+        .build()?;
+    assert_eq!(a.kind()?, SocketpairKind::Datagram);
+
+    Ok(())
+}
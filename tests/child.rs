@@ -0,0 +1,137 @@
+#![cfg(unix)]
+
+use io_extras::os::rustix::{AsRawFd, IntoRawFd};
+use socketpair::{socketpair_stream, SocketpairStream};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::net::Shutdown;
+use std::process::Command;
+use std::str;
+
+#[test]
+fn install_in_child() -> anyhow::Result<()> {
+    let (mut a, b) = socketpair_stream()?;
+
+    let mut command = Command::new("sh");
+    command.args(["-c", "read line <&3; echo \"got $line\" >&3"]);
+    SocketpairStream::install_in_child(&mut command, [(b, 3)])?;
+    let mut child = command.spawn()?;
+    drop(command);
+
+    writeln!(a, "hello")?;
+    let mut buf = String::new();
+    a.read_to_string(&mut buf)?;
+    assert_eq!(buf, "got hello\n");
+    assert!(child.wait()?.success());
+
+    Ok(())
+}
+
+#[test]
+fn install_in_child_swapped() -> anyhow::Result<()> {
+    let (mut a, b) = socketpair_stream()?;
+    let (mut c, d) = socketpair_stream()?;
+
+    // Install each stream at the number the other one has in the parent.
+    let b_fd = b.as_raw_fd();
+    let d_fd = d.as_raw_fd();
+    let mut command = Command::new("sh");
+    command.args(["-c", &format!("echo b >&{}; echo d >&{}", d_fd, b_fd)]);
+    SocketpairStream::install_in_child(&mut command, [(b, d_fd), (d, b_fd)])?;
+    let mut child = command.spawn()?;
+    drop(command);
+
+    let mut buf = String::new();
+    a.read_to_string(&mut buf)?;
+    assert_eq!(buf, "b\n");
+    buf.clear();
+    c.read_to_string(&mut buf)?;
+    assert_eq!(buf, "d\n");
+    assert!(child.wait()?.success());
+
+    Ok(())
+}
+
+#[test]
+fn from_inherited_fd() -> anyhow::Result<()> {
+    let (mut a, b) = socketpair_stream()?;
+
+    let fd = b.into_raw_fd();
+    let mut b = unsafe { SocketpairStream::from_inherited_fd(fd)? };
+    writeln!(a, "hello")?;
+    drop(a);
+    let mut buf = String::new();
+    b.read_to_string(&mut buf)?;
+    assert_eq!(buf, "hello\n");
+
+    // Files aren't sockets.
+    let file = File::open("Cargo.toml")?;
+    let fd = file.into_raw_fd();
+    assert!(unsafe { SocketpairStream::from_inherited_fd(fd) }.is_err());
+    drop(unsafe { <File as io_extras::os::rustix::FromRawFd>::from_raw_fd(fd) });
+
+    Ok(())
+}
+
+#[test]
+fn from_env() -> anyhow::Result<()> {
+    let (mut a, b) = socketpair_stream()?;
+
+    std::env::set_var("SOCKETPAIR_TEST_FROM_ENV", b.into_raw_fd().to_string());
+    let mut b = unsafe { SocketpairStream::from_env("SOCKETPAIR_TEST_FROM_ENV")? };
+    writeln!(a, "hello")?;
+    drop(a);
+    let mut buf = String::new();
+    b.read_to_string(&mut buf)?;
+    assert_eq!(buf, "hello\n");
+
+    assert!(unsafe { SocketpairStream::from_env("SOCKETPAIR_TEST_UNSET") }.is_err());
+
+    Ok(())
+}
+
+#[test]
+fn stdio() -> anyhow::Result<()> {
+    let (mut a, b) = socketpair_stream()?;
+
+    let mut child = Command::new("cat")
+        .stdin(b.try_clone()?)
+        .stdout(b)
+        .spawn()?;
+
+    writeln!(a, "hello world")?;
+    a.shutdown(Shutdown::Write)?;
+    let mut buf = String::new();
+    a.read_to_string(&mut buf)?;
+    assert_eq!(buf, "hello world\n");
+    assert!(child.wait()?.success());
+
+    Ok(())
+}
+
+#[test]
+fn install_as_stdio() -> anyhow::Result<()> {
+    let (mut a, b) = socketpair_stream()?;
+
+    let mut command = Command::new("cat");
+    b.install_as_stdio(&mut command)?;
+    let mut child = command.spawn()?;
+    drop(command);
+
+    // Round-trip a few messages through `cat`.
+    let mut buf = [0_u8; 64];
+    for msg in ["hello\n", "world\n"] {
+        a.write_all(msg.as_bytes())?;
+        let mut n = 0;
+        while n < msg.len() {
+            n += a.read(&mut buf[n..])?;
+        }
+        assert_eq!(str::from_utf8(&buf[..n]).unwrap(), msg);
+    }
+
+    a.shutdown(Shutdown::Write)?;
+    assert_eq!(a.read(&mut buf)?, 0);
+    assert!(child.wait()?.success());
+
+    Ok(())
+}
@@ -0,0 +1,106 @@
+#![cfg(unix)]
+
+use socketpair::{socketpair_dgram, SocketpairDatagram};
+use std::io;
+use std::str;
+
+#[test]
+fn send_recv() -> anyhow::Result<()> {
+    let (a, b) = socketpair_dgram()?;
+
+    a.send(b"hello world")?;
+    a.send(b"")?;
+    a.send(b"greetings")?;
+
+    let mut buf = [0_u8; 5];
+    assert_eq!(b.peek(&mut buf)?, (5, true));
+    assert_eq!(b.recv(&mut buf)?, (5, true));
+    assert_eq!(str::from_utf8(&buf).unwrap(), "hello");
+
+    let mut buf = [0_u8; 16];
+    assert_eq!(b.recv(&mut buf)?, (0, false));
+    let (n, truncated) = b.recv(&mut buf)?;
+    assert!(!truncated);
+    assert_eq!(str::from_utf8(&buf[..n]).unwrap(), "greetings");
+
+    Ok(())
+}
+
+#[test]
+fn send_buffer_size() -> anyhow::Result<()> {
+    let (a, _b) = socketpair_dgram()?;
+
+    a.set_send_buffer_size(8192)?;
+    assert!(a.send_buffer_size()? >= 8192);
+
+    // A datagram larger than the send buffer can't be sent.
+    assert!(a.send(&vec![0_u8; 0x10_0000]).is_err());
+
+    Ok(())
+}
+
+/// Unlike seqpacket sockets, datagram sockets have no end-of-stream.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+#[test]
+fn peer_closed() -> anyhow::Result<()> {
+    let (a, b) = socketpair_dgram()?;
+
+    a.send(b"hello")?;
+    drop(a);
+
+    // Queued datagrams are still delivered.
+    let mut buf = [0_u8; 16];
+    assert_eq!(b.recv(&mut buf)?, (5, false));
+
+    // After that, `recv` would wait forever instead of returning zero.
+    rustix::io::ioctl_fionbio(&b, true)?;
+    assert_eq!(
+        b.recv(&mut buf).unwrap_err().kind(),
+        io::ErrorKind::WouldBlock
+    );
+
+    // And `send` fails.
+    assert_eq!(
+        b.send(b"hello").unwrap_err().raw_os_error(),
+        Some(rustix::io::Errno::CONNREFUSED.raw_os_error())
+    );
+
+    Ok(())
+}
+
+/// On Linux, a full receive queue makes `send` wait, or fail with
+/// `WouldBlock` in nonblocking mode.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+#[test]
+fn full_queue() -> anyhow::Result<()> {
+    let (a, b) = socketpair_dgram()?;
+    rustix::io::ioctl_fionbio(&a, true)?;
+
+    let mut sent = 0;
+    let err = loop {
+        match a.send(b"hello") {
+            Ok(_) => sent += 1,
+            Err(err) => break err,
+        }
+    };
+    assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+    assert!(sent > 0);
+
+    // Receiving a datagram makes room for another.
+    let mut buf = [0_u8; 16];
+    assert_eq!(b.recv(&mut buf)?, (5, false));
+    a.send(b"hello")?;
+
+    Ok(())
+}
+
+#[test]
+fn try_from_fd() -> anyhow::Result<()> {
+    let (a, _b) = socketpair_dgram()?;
+    SocketpairDatagram::try_from_fd(a.into())?;
+
+    let (c, _d) = socketpair::socketpair_stream()?;
+    assert!(SocketpairDatagram::try_from_fd(c.into()).is_err());
+
+    Ok(())
+}
@@ -0,0 +1,104 @@
+#![cfg(unix)]
+
+use io_lifetimes::AsFd;
+use socketpair::{socketpair_seqpacket, socketpair_stream, SocketpairStream};
+use std::io::{Read, Write};
+use std::str;
+
+#[test]
+fn stream() -> anyhow::Result<()> {
+    let (a, b) = socketpair_stream()?;
+    let (mut c, d) = socketpair_stream()?;
+
+    assert_eq!(a.send_with_fds(b"hello", &[d.as_fd()])?, 5);
+    drop(d);
+
+    let mut buf = [0_u8; 16];
+    let mut fds = Vec::new();
+    let n = b.recv_with_fds(&mut buf, &mut fds)?;
+    assert_eq!(str::from_utf8(&buf[..n]).unwrap(), "hello");
+    assert_eq!(fds.len(), 1);
+
+    // The received descriptor refers to the same socket as `d`.
+    let mut d = SocketpairStream::from(fds.pop().unwrap());
+    writeln!(c, "hello world")?;
+    let n = d.read(&mut buf)?;
+    assert_eq!(str::from_utf8(&buf[..n]).unwrap(), "hello world\n");
+
+    Ok(())
+}
+
+#[test]
+fn seqpacket() -> anyhow::Result<()> {
+    let (a, b) = socketpair_seqpacket()?;
+    let (c, d) = socketpair_stream()?;
+
+    a.send_with_fds(b"two", &[c.as_fd(), d.as_fd()])?;
+    a.send_with_fds(b"none", &[])?;
+
+    let mut buf = [0_u8; 16];
+    let mut fds = Vec::new();
+    let n = b.recv_with_fds(&mut buf, &mut fds)?;
+    assert_eq!(str::from_utf8(&buf[..n]).unwrap(), "two");
+    assert_eq!(fds.len(), 2);
+
+    let n = b.recv_with_fds(&mut buf, &mut fds)?;
+    assert_eq!(str::from_utf8(&buf[..n]).unwrap(), "none");
+    assert_eq!(fds.len(), 2);
+
+    Ok(())
+}
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+#[test]
+fn credentials_with_fds() -> anyhow::Result<()> {
+    let (a, b) = socketpair_seqpacket()?;
+    let (c, _d) = socketpair_stream()?;
+
+    // Descriptors aren't dropped silently. Without `SO_PASSCRED`, the
+    // descriptor fits in the control buffer and is rejected.
+    a.send_with_fds(b"hello", &[c.as_fd()])?;
+    let mut buf = [0_u8; 16];
+    let err = b.recv_with_credentials(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+    // With `SO_PASSCRED`, the credentials and the descriptor don't both fit.
+    b.set_passcred(true)?;
+    a.send_with_fds(b"hello", &[c.as_fd()])?;
+    let err = b.recv_with_credentials(&mut buf).unwrap_err();
+    assert!(err
+        .get_ref()
+        .is_some_and(|err| err.is::<socketpair::ControlTruncated>()));
+
+    // Sending no descriptors is accepted as a plain message.
+    a.send_with_fds(b"none", &[])?;
+    let (n, _) = b.recv_with_credentials(&mut buf)?;
+    assert_eq!(str::from_utf8(&buf[..n]).unwrap(), "none");
+
+    Ok(())
+}
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+#[test]
+fn control_truncated() -> anyhow::Result<()> {
+    let (a, b) = socketpair_seqpacket()?;
+    let (c, _d) = socketpair_stream()?;
+
+    // The credentials take up room in the control buffer, so the largest
+    // number of descriptors `recv_with_fds` accepts no longer fits.
+    b.set_passcred(true)?;
+    a.send_with_fds(b"hello", &vec![c.as_fd(); 253])?;
+
+    let mut buf = [0_u8; 16];
+    let mut fds = Vec::new();
+    let err = b.recv_with_fds(&mut buf, &mut fds).unwrap_err();
+    let truncated = err
+        .get_ref()
+        .and_then(|err| err.downcast_ref::<socketpair::ControlTruncated>())
+        .unwrap();
+    assert_eq!(truncated.received(), 5);
+    assert_eq!(str::from_utf8(&buf[..5]).unwrap(), "hello");
+    assert!(fds.len() < 253);
+
+    Ok(())
+}
@@ -0,0 +1,39 @@
+#![cfg(all(unix, feature = "futures-io"))]
+
+use futures_lite::future::block_on;
+use futures_lite::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt};
+use socketpair::futures_socketpair_stream;
+use std::thread;
+
+#[test]
+fn lines() -> anyhow::Result<()> {
+    let (mut a, b) = futures_socketpair_stream()?;
+
+    // Drive the stream from a plain thread, with no runtime.
+    let t = thread::spawn(move || -> std::io::Result<()> {
+        block_on(async {
+            a.write_all(b"hello\nworld\n").await?;
+            a.write_all(b"tail").await
+        })
+    });
+
+    let mut lines = Vec::new();
+    block_on(async {
+        let mut b = b;
+        let mut line = String::new();
+        b.read_line(&mut line).await?;
+        lines.push(line.clone());
+        line.clear();
+        b.read_line(&mut line).await?;
+        lines.push(line);
+
+        t.join().unwrap()?;
+        let mut rest = vec![0_u8; 4];
+        b.read_exact(&mut rest).await?;
+        assert_eq!(rest, b"tail");
+        Ok::<_, std::io::Error>(())
+    })?;
+    assert_eq!(lines, ["hello\n", "world\n"]);
+
+    Ok(())
+}
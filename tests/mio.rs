@@ -0,0 +1,49 @@
+#![cfg(all(unix, feature = "mio"))]
+
+use mio::{Events, Interest, Poll, Token};
+use socketpair::mio_socketpair_stream;
+use std::io;
+use std::str;
+
+#[test]
+fn poll() -> anyhow::Result<()> {
+    let (mut a, mut b) = mio_socketpair_stream()?;
+
+    let mut poll = Poll::new()?;
+    poll.registry()
+        .register(&mut a, Token(0), Interest::WRITABLE)?;
+    poll.registry()
+        .register(&mut b, Token(1), Interest::READABLE)?;
+
+    // Nothing has been written yet.
+    let mut buf = [0_u8; 16];
+    assert_eq!(
+        b.try_read(&mut buf).unwrap_err().kind(),
+        io::ErrorKind::WouldBlock
+    );
+
+    let mut events = Events::with_capacity(4);
+    let mut received = String::new();
+    while received.len() < 11 {
+        poll.poll(&mut events, None)?;
+        for event in &events {
+            match event.token() {
+                Token(0) => {
+                    assert_eq!(a.try_write(b"hello world")?, 11);
+                    poll.registry().deregister(&mut a)?;
+                }
+                Token(1) => loop {
+                    match b.try_read(&mut buf) {
+                        Ok(n) => received.push_str(str::from_utf8(&buf[..n]).unwrap()),
+                        Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                        Err(err) => return Err(err.into()),
+                    }
+                },
+                _ => unreachable!(),
+            }
+        }
+    }
+    assert_eq!(received, "hello world");
+
+    Ok(())
+}
@@ -147,13 +147,13 @@ fn test_reliable() -> anyhow::Result<()> {
     // interfere with each other.
     let thread_a = thread::spawn(move || -> anyhow::Result<()> {
         for i in 0..0x8000 {
-            write!(a, "{}", format!("thread A: {}", i))?;
+            a.write_all(format!("thread A: {}", i).as_bytes())?;
         }
         Ok(())
     });
     let thread_b = thread::spawn(move || -> anyhow::Result<()> {
         for i in 0..0x8000 {
-            write!(b, "{}", format!("thread B: {}", i))?;
+            b.write_all(format!("thread B: {}", i).as_bytes())?;
         }
         Ok(())
     });
@@ -194,3 +194,106 @@ fn test_reliable() -> anyhow::Result<()> {
     thread_b.join().unwrap()?;
     Ok(())
 }
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+#[test]
+fn credentials() -> anyhow::Result<()> {
+    let (mut a, b) = socketpair_seqpacket()?;
+
+    // Without `SO_PASSCRED`, no credentials are attached.
+    assert!(!b.passcred()?);
+    writeln!(a, "hello world")?;
+    let mut buf = [0_u8; 4096];
+    let (n, cred) = b.recv_with_credentials(&mut buf)?;
+    assert_eq!(str::from_utf8(&buf[..n]).unwrap(), "hello world\n");
+    assert_eq!(cred, None);
+
+    b.set_passcred(true)?;
+    assert!(b.passcred()?);
+    writeln!(a, "goodbye")?;
+    let (n, cred) = b.recv_with_credentials(&mut buf)?;
+    assert_eq!(str::from_utf8(&buf[..n]).unwrap(), "goodbye\n");
+    let cred = cred.unwrap();
+    assert_eq!(cred.pid, std::process::id());
+    assert_eq!(cred, b.peer_credentials()?);
+
+    Ok(())
+}
+
+// `SocketpairStream` has no packet methods on Windows.
+#[cfg(unix)]
+#[test]
+fn empty_message() -> anyhow::Result<()> {
+    let (mut a, b) = socketpair_seqpacket()?;
+
+    // `write_all` would skip an empty write.
+    assert_eq!(a.write(b"")?, 0);
+    let mut buf = [0_u8; 16];
+    assert_eq!(b.recv_message(&mut buf)?, Some((0, false)));
+
+    drop(a);
+    assert_eq!(b.recv_message(&mut buf)?, None);
+
+    Ok(())
+}
+
+// `SocketpairStream` has no packet methods on Windows.
+#[cfg(unix)]
+#[test]
+fn next_message_len() -> anyhow::Result<()> {
+    let (mut a, b) = socketpair_seqpacket()?;
+
+    a.write_all(b"hello world")?;
+    a.write_all(b"greetings")?;
+
+    // `num_ready_bytes` counts every queued message.
+    assert_eq!(b.num_ready_bytes()?, 20);
+    assert_eq!(b.next_message_len()?, Some(11));
+    assert_eq!(b.recv_message_vec()?.unwrap(), b"hello world");
+    assert_eq!(b.next_message_len()?, Some(9));
+    assert_eq!(b.recv_message_vec()?.unwrap(), b"greetings");
+
+    // An empty message isn't the end of the stream.
+    assert_eq!(a.write(b"")?, 0);
+    assert_eq!(b.next_message_len()?, Some(0));
+    assert_eq!(b.recv_message_vec()?, Some(Vec::new()));
+    drop(a);
+    assert_eq!(b.next_message_len()?, None);
+    assert_eq!(b.recv_message_vec()?, None);
+
+    // Stream sockets don't have message boundaries.
+    let (_c, d) = socketpair::socketpair_stream()?;
+    assert_eq!(
+        d.next_message_len().unwrap_err().kind(),
+        io::ErrorKind::InvalidInput
+    );
+
+    Ok(())
+}
+
+/// Like `try_clone`, but detect the truncation instead of relying on it.
+/// `SocketpairStream` has no packet methods on Windows.
+#[cfg(unix)]
+#[test]
+fn truncation() -> anyhow::Result<()> {
+    let (mut a, b) = socketpair_seqpacket()?;
+
+    a.write_all(b"hello world")?;
+    a.write_all(b"hello world")?;
+    a.write_all(b"greetings")?;
+
+    let mut buf = [0_u8; 6];
+    assert_eq!(b.recv(&mut buf)?, (6, true));
+    assert_eq!(str::from_utf8(&buf).unwrap(), "hello ");
+
+    assert_eq!(
+        b.recv_strict(&mut buf).unwrap_err().kind(),
+        io::ErrorKind::InvalidData
+    );
+
+    let mut buf = [0_u8; 16];
+    let n = b.recv_strict(&mut buf)?;
+    assert_eq!(str::from_utf8(&buf[..n]).unwrap(), "greetings");
+
+    Ok(())
+}
@@ -0,0 +1,27 @@
+#![cfg(all(unix, feature = "use_smol"))]
+
+use async_io::block_on;
+use futures_lite::{AsyncReadExt, AsyncWriteExt};
+use socketpair::smol_socketpair_stream;
+use std::str;
+
+#[test]
+fn stream() -> anyhow::Result<()> {
+    block_on(async {
+        let (mut a, mut b) = smol_socketpair_stream()?;
+
+        a.write_all(b"hello world\n").await?;
+
+        let mut buf = vec![0_u8; 11];
+        assert_eq!(b.peek(&mut buf).await?, 11);
+        assert_eq!(str::from_utf8(&buf).unwrap(), "hello world");
+        assert_eq!(b.num_ready_bytes()?, 12);
+
+        drop(a);
+        let mut buf = String::new();
+        b.read_to_string(&mut buf).await?;
+        assert_eq!(buf, "hello world\n");
+
+        Ok(())
+    })
+}
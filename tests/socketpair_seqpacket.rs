@@ -0,0 +1,188 @@
+#![cfg(unix)]
+
+use socketpair::{socketpair_seqpacket_messages, SocketpairSeqpacket};
+use std::io::{IoSlice, IoSliceMut};
+use std::{str, thread};
+
+#[test]
+fn test() -> anyhow::Result<()> {
+    let (a, b) = socketpair_seqpacket_messages()?;
+
+    let thread_a = thread::spawn(move || -> anyhow::Result<()> {
+        a.send(b"hello world")?;
+
+        let mut buf = [0_u8; 4096];
+        let (n, truncated) = a.recv(&mut buf)?;
+        assert!(!truncated);
+        assert_eq!(str::from_utf8(&buf[..n]).unwrap(), "greetings");
+
+        a.send(b"goodbye")?;
+        Ok(())
+    });
+
+    let thread_b = thread::spawn(move || -> anyhow::Result<()> {
+        let mut buf = [0_u8; 4096];
+        let (n, truncated) = b.recv(&mut buf)?;
+        assert!(!truncated);
+        assert_eq!(str::from_utf8(&buf[..n]).unwrap(), "hello world");
+
+        b.send(b"greetings")?;
+
+        let (n, truncated) = b.recv(&mut buf)?;
+        assert!(!truncated);
+        assert_eq!(str::from_utf8(&buf[..n]).unwrap(), "goodbye");
+        Ok(())
+    });
+
+    thread_a.join().unwrap()?;
+    thread_b.join().unwrap()?;
+    Ok(())
+}
+
+/// Unlike reading a seqpacket `SocketpairStream` with `read_exact`, a short
+/// buffer reports truncation instead of silently losing the rest.
+#[test]
+fn truncated() -> anyhow::Result<()> {
+    let (a, b) = SocketpairSeqpacket::pair()?;
+
+    a.send(b"hello world")?;
+    a.send(b"goodbye")?;
+
+    let mut buf = [0_u8; 6];
+    assert_eq!(b.peek(&mut buf)?, (6, true));
+    assert_eq!(b.recv(&mut buf)?, (6, true));
+    assert_eq!(str::from_utf8(&buf).unwrap(), "hello ");
+
+    let mut buf = [0_u8; 16];
+    let (n, truncated) = b.recv(&mut buf)?;
+    assert!(!truncated);
+    assert_eq!(str::from_utf8(&buf[..n]).unwrap(), "goodbye");
+
+    Ok(())
+}
+
+#[test]
+fn recv_vec() -> anyhow::Result<()> {
+    let (a, b) = SocketpairSeqpacket::pair()?;
+
+    let big = vec![0xa5_u8; 10000];
+    a.send(&big)?;
+    a.send(b"small")?;
+
+    assert_eq!(b.recv_vec()?.unwrap(), big);
+    assert_eq!(b.recv_vec()?.unwrap(), b"small");
+
+    Ok(())
+}
+
+#[test]
+fn vectored() -> anyhow::Result<()> {
+    let (a, b) = SocketpairSeqpacket::pair()?;
+
+    a.send_vectored(&[IoSlice::new(b"hello "), IoSlice::new(b"world")])?;
+
+    let mut first = [0_u8; 6];
+    let mut second = [0_u8; 16];
+    let (n, truncated) =
+        b.recv_vectored(&mut [IoSliceMut::new(&mut first), IoSliceMut::new(&mut second)])?;
+    assert_eq!(n, 11);
+    assert!(!truncated);
+    assert_eq!(str::from_utf8(&first).unwrap(), "hello ");
+    assert_eq!(str::from_utf8(&second[..5]).unwrap(), "world");
+
+    Ok(())
+}
+
+/// Each clone receives whole messages. On Darwin, `SocketpairSeqpacket`
+/// uses `SOCK_DGRAM`, which has no end-of-stream.
+#[cfg(not(any(target_os = "ios", target_os = "macos")))]
+#[test]
+fn try_clone() -> anyhow::Result<()> {
+    let (a, b) = SocketpairSeqpacket::pair()?;
+    let c = b.try_clone()?;
+
+    a.send(b"hello ")?;
+    a.send(b"world")?;
+
+    let mut buf = [0_u8; 16];
+    let (n, _) = b.recv(&mut buf)?;
+    assert_eq!(str::from_utf8(&buf[..n]).unwrap(), "hello ");
+    let (n, _) = c.recv(&mut buf)?;
+    assert_eq!(str::from_utf8(&buf[..n]).unwrap(), "world");
+
+    drop(a);
+    assert_eq!(c.recv(&mut buf)?, (0, false));
+
+    Ok(())
+}
+
+// On Darwin, `SocketpairSeqpacket` uses `SOCK_DGRAM`, which has no
+// end-of-stream.
+#[cfg(not(any(target_os = "ios", target_os = "macos")))]
+#[test]
+fn empty_messages() -> anyhow::Result<()> {
+    let (a, b) = SocketpairSeqpacket::pair()?;
+
+    a.send(b"")?;
+    a.send(b"hello")?;
+    a.send(b"")?;
+
+    let mut buf = [0_u8; 16];
+    assert_eq!(b.recv_message(&mut buf)?, Some((0, false)));
+    assert_eq!(b.recv_message(&mut buf)?, Some((5, false)));
+    assert_eq!(str::from_utf8(&buf[..5]).unwrap(), "hello");
+    assert_eq!(b.recv_message(&mut buf)?, Some((0, false)));
+
+    drop(a);
+    assert_eq!(b.recv_message(&mut buf)?, None);
+    assert_eq!(b.recv_message(&mut buf)?, None);
+
+    Ok(())
+}
+
+// On Darwin, `SocketpairSeqpacket` uses `SOCK_DGRAM`, which has no
+// end-of-stream.
+#[cfg(not(any(target_os = "ios", target_os = "macos")))]
+#[test]
+fn empty_message_heartbeats() -> anyhow::Result<()> {
+    let (a, b) = SocketpairSeqpacket::pair()?;
+
+    // Empty messages sent from another thread arrive as messages.
+    let sender = thread::spawn(move || -> anyhow::Result<()> {
+        for _ in 0..3 {
+            a.send(b"")?;
+        }
+        a.send(b"done")?;
+        Ok(())
+    });
+
+    let mut buf = [0_u8; 16];
+    let mut heartbeats = 0;
+    loop {
+        match b.recv_message(&mut buf)? {
+            Some((0, false)) => heartbeats += 1,
+            Some((n, false)) => {
+                assert_eq!(str::from_utf8(&buf[..n]).unwrap(), "done");
+                break;
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+    assert_eq!(heartbeats, 3);
+
+    sender.join().unwrap()?;
+    assert_eq!(b.recv_message(&mut buf)?, None);
+
+    Ok(())
+}
+
+#[test]
+fn next_message_len() -> anyhow::Result<()> {
+    let (a, b) = SocketpairSeqpacket::pair()?;
+
+    a.send(&[0x55; 10000])?;
+    assert_eq!(b.next_message_len()?, Some(10000));
+    assert_eq!(b.recv_vec()?.unwrap(), [0x55; 10000]);
+
+    Ok(())
+}
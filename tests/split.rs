@@ -0,0 +1,67 @@
+#![cfg(unix)]
+
+use socketpair::socketpair_stream;
+use std::io::{self, Read, Write};
+use std::{str, thread};
+
+#[test]
+fn into_split() -> anyhow::Result<()> {
+    let (a, mut b) = socketpair_stream()?;
+    let (mut read, mut write) = a.into_split();
+
+    let reader = thread::spawn(move || -> io::Result<String> {
+        let mut buf = String::new();
+        read.read_to_string(&mut buf)?;
+        Ok(buf)
+    });
+    let writer = thread::spawn(move || -> io::Result<()> {
+        writeln!(write, "hello world")
+        // Dropping `write` shuts down the write direction.
+    });
+
+    let mut buf = String::new();
+    b.read_to_string(&mut buf)?;
+    assert_eq!(buf, "hello world\n");
+    writer.join().unwrap()?;
+
+    // The reverse direction still works.
+    writeln!(b, "goodbye")?;
+    drop(b);
+    assert_eq!(reader.join().unwrap()?, "goodbye\n");
+
+    Ok(())
+}
+
+#[test]
+fn reunite() -> anyhow::Result<()> {
+    let (a, mut b) = socketpair_stream()?;
+    let (c, _d) = socketpair_stream()?;
+
+    let (a_read, a_write) = a.into_split();
+    let (c_read, c_write) = c.into_split();
+
+    let err = a_read.reunite(c_write).unwrap_err();
+    let (a_read, c_write) = (err.0, err.1);
+    drop((c_read, c_write));
+
+    // Reuniting doesn't shut down the write direction.
+    let mut a = a_write.reunite(a_read)?;
+    writeln!(a, "hello world")?;
+    let mut buf = [0_u8; 4096];
+    let n = b.read(&mut buf)?;
+    assert_eq!(str::from_utf8(&buf[..n]).unwrap(), "hello world\n");
+
+    Ok(())
+}
+
+#[test]
+fn by_ref() -> anyhow::Result<()> {
+    let (a, b) = socketpair_stream()?;
+
+    writeln!(&a, "hello world")?;
+    let mut buf = [0_u8; 4096];
+    let n = (&b).read(&mut buf)?;
+    assert_eq!(str::from_utf8(&buf[..n]).unwrap(), "hello world\n");
+
+    Ok(())
+}
@@ -102,3 +102,58 @@ fn try_clone() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[cfg(any(target_os = "android", target_os = "linux"))]
+#[test]
+fn peer_credentials() -> anyhow::Result<()> {
+    let (a, b) = socketpair_stream()?;
+
+    let a_cred = a.peer_credentials()?;
+    let b_cred = b.peer_credentials()?;
+    assert_eq!(a_cred.pid, std::process::id());
+    assert_eq!(a_cred, b_cred);
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn shutdown() -> anyhow::Result<()> {
+    let (mut a, mut b) = socketpair_stream()?;
+
+    let t = thread::spawn(move || -> io::Result<()> {
+        let mut buf = String::new();
+        b.read_to_string(&mut buf)?;
+        assert_eq!(buf, "hello world\n");
+
+        writeln!(b, "goodbye")
+    });
+
+    writeln!(a, "hello world")?;
+    a.shutdown(std::net::Shutdown::Write)?;
+
+    let mut buf = String::new();
+    a.read_to_string(&mut buf)?;
+    assert_eq!(buf, "goodbye\n");
+
+    t.join().unwrap()?;
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn try_read_write() -> anyhow::Result<()> {
+    let (a, b) = socketpair_stream()?;
+
+    // These don't wait, even though the streams are in blocking mode.
+    let mut buf = [0_u8; 16];
+    assert_eq!(
+        b.try_read(&mut buf).unwrap_err().kind(),
+        io::ErrorKind::WouldBlock
+    );
+    assert_eq!(a.try_write(b"hello world")?, 11);
+    assert_eq!(b.try_read(&mut buf)?, 11);
+    assert_eq!(str::from_utf8(&buf[..11]).unwrap(), "hello world");
+
+    Ok(())
+}
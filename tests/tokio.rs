@@ -0,0 +1,120 @@
+#![cfg(all(unix, feature = "tokio"))]
+
+use io_lifetimes::AsFd;
+use socketpair::{
+    socketpair_stream, tokio_socketpair_seqpacket, tokio_socketpair_stream, SocketpairStream,
+    TokioSocketpairStream,
+};
+use std::io::{Read, Write};
+use std::str;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+#[tokio::test]
+async fn seqpacket() -> anyhow::Result<()> {
+    let (a, b) = tokio_socketpair_seqpacket().await?;
+
+    let t = tokio::spawn(async move {
+        let mut buf = [0_u8; 4096];
+        let (n, truncated) = b.recv(&mut buf).await?;
+        assert!(!truncated);
+        assert_eq!(str::from_utf8(&buf[..n]).unwrap(), "hello world");
+
+        b.send(b"greetings").await?;
+
+        let mut buf = [0_u8; 4];
+        assert_eq!(b.recv(&mut buf).await?, (4, true));
+        assert_eq!(str::from_utf8(&buf).unwrap(), "good");
+        Ok::<_, std::io::Error>(())
+    });
+
+    a.send(b"hello world").await?;
+
+    let mut buf = [0_u8; 4096];
+    let (n, truncated) = a.recv(&mut buf).await?;
+    assert!(!truncated);
+    assert_eq!(str::from_utf8(&buf[..n]).unwrap(), "greetings");
+
+    a.send(b"goodbye").await?;
+
+    t.await??;
+    Ok(())
+}
+
+#[tokio::test]
+async fn peek() -> anyhow::Result<()> {
+    let (mut a, mut b) = tokio_socketpair_stream().await?;
+
+    // Start peeking before the data is written.
+    let t = tokio::spawn(async move {
+        let mut buf = vec![0_u8; 11];
+        assert_eq!(b.peek(&mut buf).await?, 11);
+        assert_eq!(str::from_utf8(&buf).unwrap(), "hello world");
+        assert_eq!(b.num_ready_bytes()?, 12);
+
+        let mut buf = String::new();
+        b.read_to_string(&mut buf).await?;
+        assert_eq!(buf, "hello world\n");
+        Ok::<_, std::io::Error>(())
+    });
+
+    tokio::task::yield_now().await;
+    a.write_all(b"hello world\n").await?;
+    drop(a);
+
+    t.await??;
+    Ok(())
+}
+
+#[tokio::test]
+async fn fd_passing() -> anyhow::Result<()> {
+    let (a, b) = tokio_socketpair_stream().await?;
+    let (mut c, d) = socketpair_stream()?;
+
+    // Start receiving before anything is sent.
+    let t = tokio::spawn(async move {
+        let mut buf = [0_u8; 16];
+        let mut fds = Vec::new();
+        let n = b.recv_with_fds(&mut buf, &mut fds).await?;
+        assert_eq!(str::from_utf8(&buf[..n]).unwrap(), "hello");
+        assert_eq!(fds.len(), 1);
+        Ok::<_, std::io::Error>(fds.pop().unwrap())
+    });
+
+    tokio::task::yield_now().await;
+    assert_eq!(a.send_with_fds(b"hello", &[d.as_fd()]).await?, 5);
+    drop(d);
+
+    // The received descriptor refers to the same socket as `d`.
+    let mut d = SocketpairStream::from(t.await??);
+    writeln!(c, "hello world")?;
+    let mut buf = [0_u8; 16];
+    let n = d.read(&mut buf)?;
+    assert_eq!(str::from_utf8(&buf[..n]).unwrap(), "hello world\n");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn std_conversions() -> anyhow::Result<()> {
+    let (a, b) = socketpair_stream()?;
+    let mut a = TokioSocketpairStream::from_std(a)?;
+    let mut b = TokioSocketpairStream::from_std(b)?;
+
+    a.write_all(b"hello").await?;
+    let mut buf = [0_u8; 5];
+    b.read_exact(&mut buf).await?;
+    assert_eq!(&buf, b"hello");
+
+    // Back in blocking mode, a read waits for the writer thread.
+    let mut b = b.into_std()?;
+    let t = std::thread::spawn(move || -> std::io::Result<()> {
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        let mut a = a.into_std()?;
+        a.write_all(b"world")
+    });
+    b.read_exact(&mut buf)?;
+    assert_eq!(&buf, b"world");
+    t.join().unwrap()?;
+
+    Ok(())
+}
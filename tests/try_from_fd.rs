@@ -0,0 +1,73 @@
+#![cfg(unix)]
+
+use io_lifetimes::OwnedFd;
+use rustix::net::{AddressFamily, SocketType};
+use socketpair::{socketpair_seqpacket, socketpair_stream, SocketpairSeqpacket, SocketpairStream};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::net::TcpListener;
+
+#[test]
+fn stream() -> anyhow::Result<()> {
+    let (mut a, b) = socketpair_stream()?;
+    let mut b = SocketpairStream::try_from_fd(OwnedFd::from(b))?;
+
+    writeln!(a, "hello")?;
+    let mut buf = [0_u8; 6];
+    b.read_exact(&mut buf)?;
+    assert_eq!(&buf, b"hello\n");
+
+    // A stream socket doesn't have packet semantics.
+    let (_c, d) = socketpair_stream()?;
+    let d = SocketpairSeqpacket::try_from_fd(OwnedFd::from(d))
+        .unwrap_err()
+        .0;
+    let d = SocketpairStream::try_from_fd(d)?;
+    assert_eq!(
+        d.recv(&mut buf).unwrap_err().kind(),
+        io::ErrorKind::InvalidInput
+    );
+    assert_eq!(
+        d.recv_strict(&mut buf).unwrap_err().kind(),
+        io::ErrorKind::InvalidInput
+    );
+    assert_eq!(
+        d.recv_message(&mut buf).unwrap_err().kind(),
+        io::ErrorKind::InvalidInput
+    );
+
+    Ok(())
+}
+
+#[test]
+fn seqpacket() -> anyhow::Result<()> {
+    let (a, b) = socketpair_seqpacket()?;
+    let mut a = SocketpairStream::try_from_fd(OwnedFd::from(a))?;
+    let b = SocketpairSeqpacket::try_from_fd(OwnedFd::from(b))?;
+
+    b.send(b"hello")?;
+    let mut buf = [0_u8; 16];
+    assert_eq!(a.read(&mut buf)?, 5);
+
+    Ok(())
+}
+
+#[test]
+fn rejected() -> anyhow::Result<()> {
+    // The rejected descriptor is returned unchanged.
+    let file = File::open("Cargo.toml")?;
+    let fd = SocketpairStream::try_from_fd(OwnedFd::from(file))
+        .unwrap_err()
+        .0;
+    let mut contents = String::new();
+    File::from(fd).read_to_string(&mut contents)?;
+    assert!(contents.contains("socketpair"));
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    assert!(SocketpairStream::try_from_fd(OwnedFd::from(listener)).is_err());
+
+    let unconnected = rustix::net::socket(AddressFamily::UNIX, SocketType::STREAM, None)?;
+    assert!(SocketpairStream::try_from_fd(unconnected).is_err());
+
+    Ok(())
+}